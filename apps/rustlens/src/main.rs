@@ -1,17 +1,78 @@
 use anyhow::Result;
 use clap::Parser;
+use rustlens_core::model::connection::{ConnectionOptions, Driver, SslMode};
+use rustlens_core::util::conninfo;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 struct Args {
-    database_url: String,
+    /// A URL, libpq `keyword=value` settings such as `service=name`, or the
+    /// directory of a local Postgres server's Unix socket. Without one, Postgres settings come from `PGSERVICE`, `PGHOST`,
+    /// `PGUSER`, `PGDATABASE`, … and the password from `~/.pgpass`.
+    database_url: Option<String>,
 
-    #[arg(default_value = "public")]
-    schema: String,
+    /// Defaults to `public` for Postgres, `main` for SQLite and the URL's database for MySQL.
+    schema: Option<String>,
+
+    /// Unix socket directory (MySQL: the socket file), instead of the URL's host.
+    #[arg(long)]
+    host: Option<PathBuf>,
+    /// disable, allow, prefer, require, verify-ca or verify-full; overrides the URL's.
+    #[arg(long)]
+    sslmode: Option<SslMode>,
+    /// CA certificate the server's certificate must chain to.
+    #[arg(long)]
+    sslrootcert: Option<PathBuf>,
+    /// Client certificate, for servers that ask for one.
+    #[arg(long)]
+    sslcert: Option<PathBuf>,
+    /// Key of the client certificate.
+    #[arg(long)]
+    sslkey: Option<PathBuf>,
+    /// Seconds to wait for a connection before giving up.
+    #[arg(long, default_value_t = ConnectionOptions::default().connect_timeout)]
+    connect_timeout: u64,
+    /// Seconds an unused pooled connection stays open; 0 keeps it open.
+    #[arg(long, default_value_t = ConnectionOptions::default().idle_timeout)]
+    idle_timeout: u64,
+    /// Pooled connections Browse may use at once.
+    #[arg(long, default_value_t = ConnectionOptions::default().max_connections)]
+    max_connections: u32,
+    /// Shows up in `pg_stat_activity`.
+    #[arg(long, default_value_t = ConnectionOptions::default().application_name)]
+    application_name: String,
+}
+
+impl Args {
+    fn connection_options(&self) -> ConnectionOptions {
+        ConnectionOptions {
+            host: self.host.clone(),
+            sslmode: self.sslmode,
+            sslrootcert: self.sslrootcert.clone(),
+            sslcert: self.sslcert.clone(),
+            sslkey: self.sslkey.clone(),
+            connect_timeout: self.connect_timeout,
+            idle_timeout: self.idle_timeout,
+            max_connections: self.max_connections,
+            application_name: self.application_name.clone(),
+            ..ConnectionOptions::default()
+        }
+    }
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
-    validate_db_url(&args.database_url)?;
+    let mut options = args.connection_options();
+    let database_url = match args.database_url {
+        // Like `host=/var/run/postgresql`, with the rest from the environment.
+        Some(dir) if Path::new(&dir).is_dir() => {
+            options.host = Some(dir.into());
+            conninfo::conninfo_url("")?
+        }
+        Some(url) if !conninfo::is_conninfo(&url) => url,
+        conninfo => conninfo::conninfo_url(conninfo.as_deref().unwrap_or_default())?,
+    };
+    let driver = validate_db_url(&database_url)?;
 
     // Fail fast before launching the UI.
     let rt = tokio::runtime::Builder::new_current_thread()
@@ -19,26 +80,31 @@ fn main() -> Result<()> {
         .build()?;
 
     rt.block_on(async {
-        rustlens_core::db::connect::connect(&args.database_url).await?;
+        rustlens_core::db::connect::connect(driver, &database_url, &options).await?;
         Ok::<(), anyhow::Error>(())
     })?;
 
+    let schema = args
+        .schema
+        .unwrap_or_else(|| driver.default_schema(&database_url));
+
     rustlens_tui::run(rustlens_tui::LaunchMode::Viewer {
-        database_url: args.database_url,
-        schema: Some(args.schema),
+        database_url,
+        schema: Some(schema),
+        options: Box::new(options),
     })
 }
 
-fn validate_db_url(database_url: &str) -> anyhow::Result<()> {
-    let scheme = database_url
-        .split("://")
-        .next()
-        .unwrap_or("")
-        .to_ascii_lowercase();
-
-    match scheme.as_str() {
-        "postgres" | "postgresql" => Ok(()),
-        "" => anyhow::bail!("Invalid database URL (missing scheme): {database_url}"),
-        other => anyhow::bail!("Unsupported database scheme: {other}"),
+fn validate_db_url(database_url: &str) -> anyhow::Result<Driver> {
+    if let Some(driver) = Driver::from_url(database_url) {
+        return Ok(driver);
+    }
+
+    match database_url.split_once("://") {
+        Some((scheme, _)) => anyhow::bail!(
+            "Unsupported database scheme: {}",
+            scheme.to_ascii_lowercase()
+        ),
+        None => anyhow::bail!("Invalid database URL (missing scheme): {database_url}"),
     }
 }
@@ -0,0 +1,247 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use sqlx::database::HasStatementCache;
+use sqlx::{ConnectOptions, Connection, Database, Either, Executor, Pool, Row};
+use std::collections::HashMap;
+use std::sync::Mutex as SyncMutex;
+use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard};
+
+use crate::db::protocol::RequestId;
+use crate::model::catalog::{Catalog, TableEntry};
+use crate::model::connection::Driver;
+use crate::model::paging::{PageCursor, PageNav, TablePage};
+use crate::model::value::{CellValue, ColumnMeta};
+
+/// One step of a streamed SQL execution.
+#[derive(Debug)]
+pub enum SqlStep {
+    /// Emitted first, and only for statements that return rows (even zero of them).
+    Columns(Vec<ColumnMeta>),
+    Row(Vec<CellValue>),
+    /// The statement finished.
+    Done {
+        rows_affected: u64,
+    },
+}
+
+/// Maps one item of a driver's `fetch_many` stream;
+/// `Left` carries the rows affected by a finished statement.
+pub(crate) fn sql_step<R: Row>(
+    step: Either<u64, R>,
+    cell_value: fn(&R, usize) -> CellValue,
+) -> SqlStep {
+    match step {
+        Either::Left(rows_affected) => SqlStep::Done { rows_affected },
+        Either::Right(row) => SqlStep::Row((0..row.len()).map(|i| cell_value(&row, i)).collect()),
+    }
+}
+
+/// Server-side id (Postgres backend pid, MySQL connection id) of the
+/// connection currently running a statement, so a cancel can target it.
+#[derive(Debug, Default)]
+pub struct RunningQuery(SyncMutex<Option<i64>>);
+
+impl RunningQuery {
+    pub fn get(&self) -> Option<i64> {
+        *self.0.lock().unwrap()
+    }
+
+    pub fn set(&self, id: Option<i64>) {
+        *self.0.lock().unwrap() = id;
+    }
+
+    /// Marks `id` as running until the returned guard is dropped.
+    pub fn start(&self, id: i64) -> RunningGuard<'_> {
+        *self.0.lock().unwrap() = Some(id);
+        RunningGuard(self)
+    }
+}
+
+pub struct RunningGuard<'a>(&'a RunningQuery);
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        *self.0 .0.lock().unwrap() = None;
+    }
+}
+
+/// Like `RunningQuery`, for statements that run side by side, each recorded
+/// under the request it answers.
+#[derive(Debug, Default)]
+pub struct RunningQueries(SyncMutex<HashMap<RequestId, i64>>);
+
+impl RunningQueries {
+    pub fn get(&self, request: RequestId) -> Option<i64> {
+        self.0.lock().unwrap().get(&request).copied()
+    }
+
+    /// Marks `id` as running `request` until the returned guard is dropped.
+    pub fn start(&self, request: RequestId, id: i64) -> RunningEntry<'_> {
+        self.0.lock().unwrap().insert(request, id);
+        RunningEntry(self, request)
+    }
+}
+
+pub struct RunningEntry<'a>(&'a RunningQueries, RequestId);
+
+impl Drop for RunningEntry<'_> {
+    fn drop(&mut self) {
+        self.0 .0.lock().unwrap().remove(&self.1);
+    }
+}
+
+/// The SQL tab's own connection, opened from the pool's options on first use
+/// and kept until `reset`. It never goes back to the pool, so session state
+/// (SET, temp tables, search_path, an open transaction) survives between runs
+/// while Browse paging keeps using the pool.
+pub struct SessionConn<DB: Database>(Mutex<Option<DB::Connection>>);
+
+impl<DB: Database> Default for SessionConn<DB> {
+    fn default() -> Self {
+        Self(Mutex::new(None))
+    }
+}
+
+impl<DB: Database> SessionConn<DB>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    /// Locks the session connection, opening it first if needed.
+    /// Held for as long as a statement runs on it.
+    pub async fn get(&self, pool: &Pool<DB>) -> Result<MappedMutexGuard<'_, DB::Connection>> {
+        let mut guard = self.0.lock().await;
+        if guard.is_none() {
+            *guard = Some(pool.connect_options().connect().await?);
+        }
+        Ok(MutexGuard::map(guard, |c| c.as_mut().unwrap()))
+    }
+
+    /// Runs `sql` unprepared, for BEGIN / COMMIT / ROLLBACK.
+    pub async fn execute(&self, pool: &Pool<DB>, sql: &str) -> Result<()> {
+        self.get(pool).await?.execute(sql).await?;
+        Ok(())
+    }
+
+    /// Closes the connection; the next statement opens a fresh one.
+    pub async fn reset(&self) -> Result<()> {
+        if let Some(conn) = self.0.lock().await.take() {
+            conn.close().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Prepares `sql` on `conn` for its description only, and drops it from the
+/// connection's statement cache again: a cached statement outlives the
+/// `ALTER TABLE` or `USE` that changes what it means. The statement itself
+/// is then run unprepared.
+pub(crate) async fn describe<'q, DB: Database + HasStatementCache>(
+    conn: &mut DB::Connection,
+    sql: &'q str,
+) -> Result<DB::Statement<'q>>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    let statement = conn.prepare(sql).await?;
+    conn.clear_cached_statements().await?;
+    Ok(statement)
+}
+
+/// Everything the worker needs from a database.
+/// One implementation per `Driver`; the worker only ever talks to this trait.
+#[async_trait]
+pub trait DatabaseBackend: Send + Sync {
+    fn driver(&self) -> Driver;
+
+    async fn list_schemas(&self) -> Result<Vec<String>>;
+
+    /// Everything in `schema` that can be browsed: tables, views and, where the
+    /// server has them, materialized views, partitioned and foreign tables.
+    async fn list_tables(&self, schema: &str) -> Result<Vec<TableEntry>>;
+
+    /// Relations of `schema` with their columns, keys, indexes and checks,
+    /// plus the list of all schemas.
+    async fn load_catalog(&self, schema: &str) -> Result<Catalog>;
+
+    /// Loads the page `nav` points at from the one described by `cursor`.
+    /// Tables with a primary key or usable unique index are ordered by it and
+    /// paged by key; others fall back to OFFSET.
+    async fn load_table_page(
+        &self,
+        schema: &str,
+        table: &str,
+        nav: PageNav,
+        cursor: &PageCursor,
+        page_size: i64,
+    ) -> Result<TablePage>;
+
+    /// The planner's idea of how many rows `table` has: cheap, possibly stale.
+    /// `None` when the database keeps no such statistic.
+    async fn estimate_rows(&self, _schema: &str, _table: &str) -> Result<Option<i64>> {
+        Ok(None)
+    }
+
+    /// Exact `count(*)` for `request`. It can be stopped with `cancel_count`.
+    async fn count_rows(&self, request: RequestId, schema: &str, table: &str) -> Result<i64>;
+
+    /// `REFRESH MATERIALIZED VIEW`. Only Postgres has them.
+    async fn refresh_materialized_view(&self, _schema: &str, _table: &str) -> Result<()> {
+        anyhow::bail!("This database has no materialized views.")
+    }
+
+    /// The type of each positional parameter (`$1`, `?`) of `sql`, as SQL
+    /// spells it, from preparing it on the session connection. Empty when
+    /// the driver does not infer parameter types.
+    async fn describe_params(&self, _sql: &str) -> Result<Vec<Option<String>>> {
+        Ok(Vec::new())
+    }
+
+    /// Runs `sql` once on the session connection and streams its rows as they arrive,
+    /// so callers can stop reading without buffering the whole result.
+    /// The statement is described first; the description decides whether
+    /// a `Columns` step (a result set) is emitted at all. It then runs
+    /// unprepared, so nothing of it is cached on the connection. `params`
+    /// are bound as text to its positional parameters, `None` as NULL.
+    fn execute_sql<'a>(
+        &'a self,
+        sql: &'a str,
+        params: &'a [Option<String>],
+    ) -> BoxStream<'a, Result<SqlStep>>;
+
+    /// Called once the caller is done with an `execute_sql` stream, whether it
+    /// ran to the end or was dropped early, to release what the statement
+    /// still holds on the server, so rows nobody reads are never sent.
+    async fn finish_sql(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Asks the server to stop the statement currently running in `execute_sql`.
+    /// Returns false when nothing was running or the driver has no way to cancel;
+    /// dropping the stream is then the only way to stop it.
+    async fn cancel(&self) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Like `cancel`, for the `count_rows` of `request`. Counts run on
+    /// connections of their own, so stopping one leaves the rest alone.
+    async fn cancel_count(&self, _request: RequestId) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Runs `sql` (BEGIN, START TRANSACTION, …) on the session connection
+    /// `execute_sql` uses, so the following statements join the transaction.
+    async fn begin(&self, sql: &str) -> Result<()>;
+
+    async fn commit(&self) -> Result<()>;
+
+    async fn rollback(&self) -> Result<()>;
+
+    /// Rolls the open transaction back to `savepoint`. Unlike `execute_sql`,
+    /// this works once a failed statement has aborted the transaction.
+    async fn rollback_to(&self, savepoint: &str) -> Result<()>;
+
+    /// Closes the session connection, dropping its settings, temp tables and
+    /// any open transaction. The next `execute_sql` starts on a fresh one.
+    async fn reset_session(&self) -> Result<()>;
+}
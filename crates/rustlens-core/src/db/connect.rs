@@ -1,10 +1,106 @@
-use anyhow::{Context, Result};
-use sqlx::postgres::{PgPool, PgPoolOptions};
+use anyhow::{bail, Context, Result};
+use sqlx::mysql::MySqlConnectOptions;
+use sqlx::pool::{Pool, PoolOptions};
+use sqlx::postgres::PgConnectOptions;
+use sqlx::{Connection, Database};
 
-pub async fn connect(database_url: &str) -> Result<PgPool> {
-    PgPoolOptions::new()
-        .max_connections(6)
-        .connect(database_url)
+use crate::db::backend::DatabaseBackend;
+use crate::db::mysql::MysqlBackend;
+use crate::db::postgres::PostgresBackend;
+use crate::db::sqlite::SqliteBackend;
+use crate::db::tunnel::Tunnel;
+use crate::model::connection::{ConnectionOptions, Driver};
+
+pub async fn connect(
+    driver: Driver,
+    database_url: &str,
+    options: &ConnectionOptions,
+) -> Result<Box<dyn DatabaseBackend>> {
+    match driver {
+        Driver::Postgres => Ok(Box::new(
+            PostgresBackend::connect(database_url, options).await?,
+        )),
+        Driver::Sqlite => Ok(Box::new(
+            SqliteBackend::connect(database_url, options).await?,
+        )),
+        Driver::Mysql => Ok(Box::new(
+            MysqlBackend::connect(database_url, options).await?,
+        )),
+    }
+}
+
+/// Connect options of a server reached over TCP, which an SSH tunnel can
+/// stand in for.
+pub(crate) trait TcpOptions: Sized {
+    fn is_socket(&self) -> bool;
+    fn host(&self) -> &str;
+    fn port(&self) -> u16;
+    fn forward_to(self, local_port: u16) -> Self;
+}
+
+impl TcpOptions for PgConnectOptions {
+    fn is_socket(&self) -> bool {
+        self.get_socket().is_some()
+    }
+    fn host(&self) -> &str {
+        self.get_host()
+    }
+    fn port(&self) -> u16 {
+        self.get_port()
+    }
+    fn forward_to(self, local_port: u16) -> Self {
+        self.host("127.0.0.1").port(local_port)
+    }
+}
+
+impl TcpOptions for MySqlConnectOptions {
+    fn is_socket(&self) -> bool {
+        self.get_socket().is_some()
+    }
+    fn host(&self) -> &str {
+        self.get_host()
+    }
+    fn port(&self) -> u16 {
+        self.get_port()
+    }
+    fn forward_to(self, local_port: u16) -> Self {
+        self.host("127.0.0.1").port(local_port)
+    }
+}
+
+/// The pool of a Postgres or MySQL backend, sized and timed out as
+/// `options` says and reached through the SSH tunnel `options.ssh` asks
+/// for. The pool keeps the tunnel, so it closes after the last connection.
+pub(crate) async fn open_pool<DB>(
+    opts: <DB::Connection as Connection>::Options,
+    options: &ConnectionOptions,
+) -> Result<Pool<DB>>
+where
+    DB: Database,
+    <DB::Connection as Connection>::Options: TcpOptions,
+{
+    let (opts, tunnel) = match &options.ssh {
+        Some(ssh) => {
+            if opts.is_socket() {
+                bail!("An SSH tunnel needs the database's TCP host, not a Unix socket.");
+            }
+            let timeout = options.connect_timeout();
+            let tunnel = Tunnel::open(ssh, opts.host(), opts.port(), timeout).await?;
+            (opts.forward_to(tunnel.local_port()), Some(tunnel))
+        }
+        None => (opts, None),
+    };
+
+    PoolOptions::<DB>::new()
+        .max_connections(options.max_connections)
+        .acquire_timeout(options.connect_timeout())
+        .idle_timeout(options.idle_timeout())
+        // Only here to tie the tunnel's lifetime to the pool's.
+        .after_connect(move |_, _| {
+            let _ = &tunnel;
+            Box::pin(async { Ok(()) })
+        })
+        .connect_with(opts)
         .await
         .context("failed to connect to database")
 }
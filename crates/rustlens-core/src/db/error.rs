@@ -0,0 +1,101 @@
+use std::fmt;
+
+use sqlx::postgres::{PgDatabaseError, PgErrorPosition};
+
+/// A failed command, with whatever diagnostics the server sent.
+/// Only `message` is always present; the rest depends on driver and error.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DbError {
+    /// SQLSTATE (`42P01`, `23505`, …); SQLite reports its result code here.
+    pub code: Option<String>,
+    pub message: String,
+    pub detail: Option<String>,
+    pub hint: Option<String>,
+    /// 1-based character offset into the statement.
+    pub position: Option<usize>,
+    pub constraint: Option<String>,
+    pub kind: DbErrorKind,
+}
+
+/// What the client can do about an error beyond showing it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DbErrorKind {
+    #[default]
+    Other,
+    /// The schema a command named is not on the server.
+    SchemaNotFound,
+}
+
+/// Returned by backends asked for a schema that does not exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaNotFound(pub String);
+
+impl fmt::Display for SchemaNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, r#"schema "{}" does not exist"#, self.0)
+    }
+}
+
+impl std::error::Error for SchemaNotFound {}
+
+impl DbError {
+    pub fn msg(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Picks the database error out of `err`'s chain if there is one,
+    /// otherwise keeps the whole chain as the message.
+    pub fn from_anyhow(err: &anyhow::Error) -> Self {
+        if let Some(e) = err.chain().find_map(|e| e.downcast_ref::<DbError>()) {
+            return e.clone();
+        }
+        if let Some(e) = err.chain().find_map(|e| e.downcast_ref::<SchemaNotFound>()) {
+            return Self {
+                message: e.to_string(),
+                kind: DbErrorKind::SchemaNotFound,
+                ..Self::default()
+            };
+        }
+
+        let db_err = err
+            .chain()
+            .find_map(|e| e.downcast_ref::<sqlx::Error>())
+            .and_then(|e| e.as_database_error());
+
+        let Some(db_err) = db_err else {
+            return Self::msg(format!("{err:#}"));
+        };
+
+        let mut out = Self {
+            code: db_err.code().map(|c| c.into_owned()),
+            message: db_err.message().to_string(),
+            constraint: db_err.constraint().map(str::to_string),
+            ..Self::default()
+        };
+
+        if let Some(pg) = db_err.try_downcast_ref::<PgDatabaseError>() {
+            out.detail = pg.detail().map(str::to_string);
+            out.hint = pg.hint().map(str::to_string);
+            out.position = match pg.position() {
+                Some(PgErrorPosition::Original(p)) => Some(p),
+                _ => None,
+            };
+        }
+
+        out
+    }
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.code {
+            Some(code) => write!(f, "{} ({code})", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
@@ -1,6 +1,15 @@
+pub mod backend;
 pub mod connect;
+pub mod error;
+pub mod mysql;
+pub(crate) mod paging;
 pub mod postgres;
 pub mod protocol;
+pub mod sqlite;
+pub mod tunnel;
 pub mod worker;
 
-pub use protocol::{DbCmd, DbEvt};
+pub use backend::DatabaseBackend;
+pub use error::{DbError, DbErrorKind, SchemaNotFound};
+pub use protocol::{DbClient, DbCmd, DbEvt, DbReply, DbRequest, Pane, RequestId};
+pub use protocol::{StatementOutcome, TxStatus};
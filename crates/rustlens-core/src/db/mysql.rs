@@ -0,0 +1,795 @@
+use crate::db::backend::{
+    describe, sql_step, DatabaseBackend, RunningQueries, RunningQuery, SessionConn, SqlStep,
+};
+use crate::db::connect::open_pool;
+use crate::db::error::SchemaNotFound;
+use crate::db::paging::{keyset_page, keyset_query, offset_index, KeyColumn};
+use crate::db::protocol::RequestId;
+use crate::model::catalog::{
+    Catalog, CheckConstraint, Column, ForeignKey, Identity, Index, PrimaryKey, Relation,
+    RelationKind, TableEntry,
+};
+use crate::model::connection::{ConnectionOptions, Driver, SslMode};
+use crate::model::paging::{PageCursor, PageNav, PageOrder, PagePos, TablePage};
+use crate::model::value::ColumnMeta;
+use crate::util::decode::{mysql_cell_value, mysql_column_meta};
+use anyhow::{Context, Result};
+use async_stream::try_stream;
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
+use sqlx::mysql::{
+    MySqlConnectOptions, MySqlConnection, MySqlDatabaseError, MySqlPool, MySqlSslMode,
+};
+use sqlx::{Either, Executor as _, MySql, Row as _, Statement as _};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+pub struct MysqlBackend {
+    pool: MySqlPool,
+    session: SessionConn<MySql>,
+    /// The session connection while `execute_sql` runs on it.
+    running: RunningQuery,
+    /// The pooled connection of each `count_rows`.
+    counting: RunningQueries,
+    /// The session connection while the server still has rows of an
+    /// `execute_sql` result to send.
+    unread: RunningQuery,
+}
+
+impl MysqlBackend {
+    pub async fn connect(database_url: &str, options: &ConnectionOptions) -> Result<Self> {
+        let opts = connect_options(database_url, options)?;
+        let pool = open_pool::<MySql>(opts, options).await?;
+        Ok(Self {
+            pool,
+            running: RunningQuery::default(),
+            counting: RunningQueries::default(),
+            unread: RunningQuery::default(),
+            session: SessionConn::default(),
+        })
+    }
+}
+
+/// The URL's settings, with the profile's socket, TLS files and mode laid
+/// over them. MySQL has no application name to set.
+fn connect_options(database_url: &str, options: &ConnectionOptions) -> Result<MySqlConnectOptions> {
+    let mut opts = MySqlConnectOptions::from_str(database_url).context("invalid MySQL URL")?;
+    if let Some(path) = &options.host {
+        opts = opts.socket(path);
+    }
+    if let Some(mode) = options.sslmode {
+        opts = opts.ssl_mode(match mode {
+            SslMode::Disable => MySqlSslMode::Disabled,
+            SslMode::Allow | SslMode::Prefer => MySqlSslMode::Preferred,
+            SslMode::Require => MySqlSslMode::Required,
+            SslMode::VerifyCa => MySqlSslMode::VerifyCa,
+            SslMode::VerifyFull => MySqlSslMode::VerifyIdentity,
+        });
+    }
+    if let Some(path) = &options.sslrootcert {
+        opts = opts.ssl_ca(path);
+    }
+    if let Some(path) = &options.sslcert {
+        opts = opts.ssl_client_cert(path);
+    }
+    if let Some(path) = &options.sslkey {
+        opts = opts.ssl_client_key(path);
+    }
+    Ok(opts)
+}
+
+#[async_trait]
+impl DatabaseBackend for MysqlBackend {
+    fn driver(&self) -> Driver {
+        Driver::Mysql
+    }
+
+    async fn list_schemas(&self) -> Result<Vec<String>> {
+        load_schemas(&self.pool).await
+    }
+
+    async fn list_tables(&self, schema: &str) -> Result<Vec<TableEntry>> {
+        load_tables(&self.pool, schema).await
+    }
+
+    async fn load_catalog(&self, schema: &str) -> Result<Catalog> {
+        load_catalog(&self.pool, schema).await
+    }
+
+    async fn load_table_page(
+        &self,
+        schema: &str,
+        table: &str,
+        nav: PageNav,
+        cursor: &PageCursor,
+        page_size: i64,
+    ) -> Result<TablePage> {
+        load_table_page(&self.pool, schema, table, nav, cursor, page_size).await
+    }
+
+    async fn estimate_rows(&self, schema: &str, table: &str) -> Result<Option<i64>> {
+        estimate_rows(&self.pool, schema, table).await
+    }
+
+    async fn count_rows(&self, request: RequestId, schema: &str, table: &str) -> Result<i64> {
+        count_rows(&self.pool, &self.counting, request, schema, table).await
+    }
+
+    fn execute_sql<'a>(
+        &'a self,
+        sql: &'a str,
+        params: &'a [Option<String>],
+    ) -> BoxStream<'a, Result<SqlStep>> {
+        execute_sql(
+            &self.pool,
+            &self.running,
+            &self.unread,
+            &self.session,
+            sql,
+            params,
+        )
+    }
+
+    /// MySQL has no cursors for plain queries, and the server would send the
+    /// rest of an abandoned result before the next statement; stop it instead.
+    async fn finish_sql(&self) -> Result<()> {
+        kill_query(&self.pool, self.unread.get()).await?;
+        self.unread.set(None);
+        Ok(())
+    }
+
+    async fn cancel(&self) -> Result<bool> {
+        kill_query(&self.pool, self.running.get()).await
+    }
+
+    async fn cancel_count(&self, request: RequestId) -> Result<bool> {
+        kill_query(&self.pool, self.counting.get(request)).await
+    }
+
+    async fn begin(&self, sql: &str) -> Result<()> {
+        self.session.execute(&self.pool, sql).await
+    }
+
+    async fn commit(&self) -> Result<()> {
+        self.session.execute(&self.pool, "COMMIT").await
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        self.session.execute(&self.pool, "ROLLBACK").await
+    }
+
+    async fn rollback_to(&self, savepoint: &str) -> Result<()> {
+        let sql = format!("ROLLBACK TO SAVEPOINT {savepoint}");
+        self.session.execute(&self.pool, &sql).await
+    }
+
+    async fn reset_session(&self) -> Result<()> {
+        self.session.reset().await
+    }
+}
+
+// information_schema columns come back with a binary collation on some
+// servers, so they are cast to CHAR to decode as String.
+
+pub async fn load_schemas(pool: &MySqlPool) -> Result<Vec<String>> {
+    let rows = sqlx::query(
+        r#"
+        select cast(schema_name as char) as schema_name
+        from information_schema.schemata
+        where schema_name not in ('information_schema', 'mysql', 'performance_schema', 'sys')
+        order by schema_name
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| r.get::<String, _>("schema_name"))
+        .collect())
+}
+
+pub async fn load_tables(pool: &MySqlPool, schema: &str) -> Result<Vec<TableEntry>> {
+    let exists = schema_exists(pool, schema).await?;
+
+    if !exists {
+        return Err(SchemaNotFound(schema.to_string()).into());
+    }
+    let rows = sqlx::query(
+        r#"
+        select cast(table_name as char) as table_name,
+               cast(table_type as char) as table_type
+        from information_schema.tables
+        where table_schema = ?
+          and table_type in ('BASE TABLE', 'VIEW')
+        order by table_name
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| TableEntry {
+            name: r.get("table_name"),
+            kind: table_kind(&r.get::<String, _>("table_type")),
+        })
+        .collect())
+}
+
+fn quote_ident(s: &str) -> String {
+    format!("`{}`", s.replace('`', "``"))
+}
+
+/// `information_schema.tables.table_type`.
+fn table_kind(table_type: &str) -> RelationKind {
+    match table_type {
+        "VIEW" => RelationKind::View,
+        _ => RelationKind::Table,
+    }
+}
+
+/// `column_default` as SQL text. MySQL reports literal defaults bare (`abc`,
+/// not `'abc'`) and flags expressions as `DEFAULT_GENERATED`; MariaDB quotes
+/// literals already and spells a missing default `NULL`.
+fn default_sql(value: String, data_type: &str, extra: &str) -> Option<String> {
+    let numeric = ["int", "decimal", "float", "double", "bit"]
+        .iter()
+        .any(|t| data_type.contains(t));
+    if value == "NULL" {
+        None
+    } else if extra.contains("default_generated")
+        || value.starts_with('\'')
+        || (numeric && value.parse::<f64>().is_ok())
+        || value.to_ascii_lowercase().starts_with("current_timestamp")
+    {
+        Some(value)
+    } else {
+        Some(format!("'{}'", value.replace('\'', "''")))
+    }
+}
+
+/// Relations of `schema` with their columns, keys, indexes and checks,
+/// read from `information_schema`.
+pub async fn load_catalog(pool: &MySqlPool, schema: &str) -> Result<Catalog> {
+    if !schema_exists(pool, schema).await? {
+        return Err(SchemaNotFound(schema.to_string()).into());
+    }
+    let mut catalog = Catalog {
+        schemas: load_schemas(pool).await?,
+        schema: schema.to_string(),
+        relations: Vec::new(),
+    };
+
+    let rows = sqlx::query(
+        r#"
+        select cast(t.table_name as char) as table_name,
+               cast(t.table_type as char) as table_type,
+               cast(t.table_comment as char) as table_comment,
+               cast(v.view_definition as char) as view_definition
+        from information_schema.tables t
+        left join information_schema.views v
+          on v.table_schema = t.table_schema and v.table_name = t.table_name
+        where t.table_schema = ?
+          and t.table_type in ('BASE TABLE', 'VIEW')
+        order by t.table_name
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    for r in rows {
+        let kind = table_kind(&r.get::<String, _>("table_type"));
+        let mut rel = Relation::new(r.get("table_name"), kind);
+        // Views carry the comment `VIEW`.
+        if kind == RelationKind::Table {
+            rel.comment = r
+                .get::<Option<String>, _>("table_comment")
+                .filter(|c| !c.is_empty());
+        }
+        rel.view_query = r.get("view_definition");
+        catalog.relations.push(rel);
+    }
+    let index: HashMap<String, usize> = catalog
+        .relations
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (r.name.clone(), i))
+        .collect();
+    let relation = |name: &str| index.get(name).copied();
+
+    let rows = sqlx::query(
+        r#"
+        select cast(table_name as char) as table_name,
+               cast(column_name as char) as column_name,
+               cast(column_type as char) as column_type,
+               cast(is_nullable as char) as is_nullable,
+               cast(column_default as char) as column_default,
+               cast(extra as char) as extra,
+               cast(generation_expression as char) as generation_expression,
+               cast(column_comment as char) as column_comment
+        from information_schema.columns
+        where table_schema = ?
+        order by table_name, ordinal_position
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    for r in rows {
+        let Some(i) = relation(&r.get::<String, _>("table_name")) else {
+            continue;
+        };
+        let extra = r
+            .get::<Option<String>, _>("extra")
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let generated = r
+            .get::<Option<String>, _>("generation_expression")
+            .filter(|e| !e.is_empty());
+        let data_type: String = r.get("column_type");
+        let default = r
+            .get::<Option<String>, _>("column_default")
+            .and_then(|d| default_sql(d, &data_type, &extra));
+        catalog.relations[i].columns.push(Column {
+            name: r.get("column_name"),
+            data_type,
+            nullable: r.get::<String, _>("is_nullable") == "YES",
+            default,
+            identity: extra
+                .contains("auto_increment")
+                .then_some(Identity::ByDefault),
+            generated,
+            comment: r
+                .get::<Option<String>, _>("column_comment")
+                .filter(|c| !c.is_empty()),
+        });
+    }
+
+    // Primary keys and unique or plain indexes, one row per key part.
+    let rows = sqlx::query(
+        r#"
+        select cast(table_name as char) as table_name,
+               cast(index_name as char) as index_name,
+               cast(column_name as char) as column_name,
+               non_unique
+        from information_schema.statistics
+        where table_schema = ?
+        order by table_name, index_name, seq_in_index
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    for r in rows {
+        let Some(i) = relation(&r.get::<String, _>("table_name")) else {
+            continue;
+        };
+        let rel = &mut catalog.relations[i];
+        let name: String = r.get("index_name");
+        // Functional key parts have no column name.
+        let column = r
+            .get::<Option<String>, _>("column_name")
+            .unwrap_or_else(|| "(expression)".to_string());
+
+        match rel.indexes.last_mut() {
+            Some(ix) if ix.name == name => ix.columns.push(column),
+            _ => rel.indexes.push(Index {
+                primary: name == "PRIMARY",
+                unique: r.get::<i64, _>("non_unique") == 0,
+                name,
+                columns: vec![column],
+                predicate: None,
+                definition: None,
+            }),
+        }
+    }
+    for rel in &mut catalog.relations {
+        rel.primary_key = rel
+            .indexes
+            .iter()
+            .find(|ix| ix.primary)
+            .map(|ix| PrimaryKey {
+                name: Some(ix.name.clone()),
+                columns: ix.columns.clone(),
+            });
+    }
+
+    let rows = sqlx::query(
+        r#"
+        select cast(k.table_name as char) as table_name,
+               cast(k.constraint_name as char) as constraint_name,
+               cast(k.column_name as char) as column_name,
+               cast(k.referenced_table_schema as char) as ref_schema,
+               cast(k.referenced_table_name as char) as ref_table,
+               cast(k.referenced_column_name as char) as ref_column,
+               cast(rc.update_rule as char) as update_rule,
+               cast(rc.delete_rule as char) as delete_rule
+        from information_schema.key_column_usage k
+        join information_schema.referential_constraints rc
+          on rc.constraint_schema = k.constraint_schema
+         and rc.constraint_name = k.constraint_name
+         and rc.table_name = k.table_name
+        where k.table_schema = ?
+          and k.referenced_table_name is not null
+        order by k.table_name, k.constraint_name, k.ordinal_position
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    for r in rows {
+        let Some(i) = relation(&r.get::<String, _>("table_name")) else {
+            continue;
+        };
+        let rel = &mut catalog.relations[i];
+        let name: String = r.get("constraint_name");
+        let column: String = r.get("column_name");
+        let ref_column: String = r.get("ref_column");
+
+        match rel.foreign_keys.last_mut() {
+            Some(fk) if fk.name.as_deref() == Some(name.as_str()) => {
+                fk.columns.push(column);
+                fk.ref_columns.push(ref_column);
+            }
+            _ => rel.foreign_keys.push(ForeignKey {
+                name: Some(name),
+                columns: vec![column],
+                ref_schema: r.get("ref_schema"),
+                ref_table: r.get("ref_table"),
+                ref_columns: vec![ref_column],
+                on_update: r.get("update_rule"),
+                on_delete: r.get("delete_rule"),
+            }),
+        }
+    }
+
+    // check_constraints only exists from MySQL 8.0.16 / MariaDB 10.2; older
+    // servers lack the table, which only means there are no checks to show.
+    let rows = sqlx::query(
+        r#"
+        select cast(t.table_name as char) as table_name,
+               cast(c.constraint_name as char) as constraint_name,
+               cast(c.check_clause as char) as check_clause
+        from information_schema.check_constraints c
+        join information_schema.table_constraints t
+          on t.constraint_schema = c.constraint_schema
+         and t.constraint_name = c.constraint_name
+         and t.constraint_type = 'CHECK'
+        where c.constraint_schema = ?
+        order by t.table_name, c.constraint_name
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await;
+    let rows = match rows {
+        Ok(rows) => rows,
+        Err(e) if matches!(error_number(&e), Some(ER_NO_SUCH_TABLE | ER_UNKNOWN_TABLE)) => {
+            Vec::new()
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    for r in rows {
+        let Some(i) = relation(&r.get::<String, _>("table_name")) else {
+            continue;
+        };
+        catalog.relations[i].checks.push(CheckConstraint {
+            name: Some(r.get("constraint_name")),
+            expression: r.get("check_clause"),
+        });
+    }
+
+    Ok(catalog)
+}
+
+pub async fn load_table_page(
+    pool: &MySqlPool,
+    schema: &str,
+    table: &str,
+    nav: PageNav,
+    cursor: &PageCursor,
+    page_size: i64,
+) -> Result<TablePage> {
+    let qualified = format!("{}.{}", quote_ident(schema), quote_ident(table));
+    let key = load_page_key(pool, schema, table).await?;
+
+    if key.is_empty() {
+        // MySQL has no physical row address to order by; pages follow the
+        // server's scan order.
+        let count = format!("select count(*) from {qualified}");
+        let index = offset_index(nav, cursor, page_size, async {
+            Ok(sqlx::query_scalar::<_, i64>(&count).fetch_one(pool).await?)
+        })
+        .await?;
+
+        let sql = format!("select * from {qualified} limit ? offset ?");
+        let rows = sqlx::query(&sql)
+            .bind(page_size)
+            .bind(index * page_size)
+            .fetch_all(pool)
+            .await?;
+
+        let columns: Vec<ColumnMeta> = rows
+            .first()
+            .map(|r| r.columns().iter().map(mysql_column_meta).collect())
+            .unwrap_or_default();
+        let at_end = nav == PageNav::Last || (rows.len() as i64) < page_size;
+        let rows = rows
+            .iter()
+            .map(|r| (0..columns.len()).map(|i| mysql_cell_value(r, i)).collect())
+            .collect();
+
+        return Ok(TablePage {
+            columns,
+            rows,
+            order: PageOrder::Offset,
+            cursor: PageCursor {
+                pos: PagePos::FromStart(index),
+                at_end,
+                ..PageCursor::default()
+            },
+        });
+    }
+
+    let columns: Vec<KeyColumn> = key
+        .iter()
+        .map(|part| KeyColumn {
+            ident: quote_ident(&part.name),
+            text: format!("cast(p.{} as char)", quote_ident(&part.name)),
+            // Comparing a number with a string goes through DOUBLE and loses
+            // precision on large keys, so numbers are cast back first.
+            param: match part.data_type.as_str() {
+                "tinyint" | "smallint" | "mediumint" | "int" | "bigint"
+                    if part.column_type.contains("unsigned") =>
+                {
+                    "cast(? as unsigned)".to_string()
+                }
+                "tinyint" | "smallint" | "mediumint" | "int" | "bigint" => {
+                    "cast(? as signed)".to_string()
+                }
+                "decimal" => "cast(? as decimal(65, 30))".to_string(),
+                _ => "?".to_string(),
+            },
+        })
+        .collect();
+    let query = keyset_query(&qualified, &columns, nav, cursor, page_size);
+
+    let mut q = sqlx::query(&query.sql);
+    for v in &query.binds {
+        q = q.bind(v);
+    }
+    let rows = q.fetch_all(pool).await?;
+
+    let (columns, rows, cursor) =
+        keyset_page(rows, key.len(), &query, mysql_column_meta, mysql_cell_value);
+    Ok(TablePage {
+        columns,
+        rows,
+        order: PageOrder::Key(key.into_iter().map(|part| part.name).collect()),
+        cursor,
+    })
+}
+
+/// A column of the key `load_table_page` orders by.
+#[derive(Clone)]
+struct KeyPart {
+    name: String,
+    /// `information_schema.columns.data_type`, e.g. `int`.
+    data_type: String,
+    /// The full type, e.g. `int unsigned`.
+    column_type: String,
+}
+
+/// The primary key, else the narrowest unique index over NOT NULL columns.
+/// Empty if there is none.
+async fn load_page_key(pool: &MySqlPool, schema: &str, table: &str) -> Result<Vec<KeyPart>> {
+    let rows = sqlx::query(
+        r#"
+        select cast(s.index_name as char) as index_name,
+               cast(s.column_name as char) as column_name,
+               cast(c.is_nullable as char) as is_nullable,
+               cast(c.data_type as char) as data_type,
+               cast(c.column_type as char) as column_type
+        from information_schema.statistics s
+        left join information_schema.columns c
+               on c.table_schema = s.table_schema
+              and c.table_name = s.table_name
+              and c.column_name = s.column_name
+        where s.table_schema = ?
+          and s.table_name = ?
+          and s.non_unique = 0
+        order by s.index_name = 'PRIMARY' desc, s.index_name, s.seq_in_index
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
+
+    // (index name, usable, columns), in the order the rows came in.
+    let mut indexes: Vec<(String, bool, Vec<KeyPart>)> = Vec::new();
+    for r in rows {
+        let index: String = r.get("index_name");
+        // Functional index parts have no column.
+        let column: Option<String> = r.get("column_name");
+        let nullable: Option<String> = r.get("is_nullable");
+        let usable = column.is_some() && nullable.as_deref() == Some("NO");
+        let col = KeyPart {
+            name: column.unwrap_or_default(),
+            data_type: r.get::<Option<String>, _>("data_type").unwrap_or_default(),
+            column_type: r
+                .get::<Option<String>, _>("column_type")
+                .unwrap_or_default(),
+        };
+
+        match indexes.last_mut() {
+            Some((name, ok, cols)) if *name == index => {
+                *ok &= usable;
+                cols.push(col);
+            }
+            _ => indexes.push((index, usable, vec![col])),
+        }
+    }
+
+    // The primary key sorts first; otherwise take the narrowest index.
+    let primary = indexes
+        .first()
+        .filter(|(name, ok, _)| name == "PRIMARY" && *ok);
+    let best = primary.or_else(|| {
+        indexes
+            .iter()
+            .filter(|(_, ok, _)| *ok)
+            .min_by_key(|(_, _, cols)| cols.len())
+    });
+    Ok(best.map(|(_, _, cols)| cols.clone()).unwrap_or_default())
+}
+
+/// `table_rows` from the data dictionary; InnoDB only samples it.
+pub async fn estimate_rows(pool: &MySqlPool, schema: &str, table: &str) -> Result<Option<i64>> {
+    let rows: Option<Option<i64>> = sqlx::query_scalar(
+        r#"
+        select cast(table_rows as signed)
+        from information_schema.tables
+        where table_schema = ?
+          and table_name = ?
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(rows.flatten())
+}
+
+/// "This command is not supported in the prepared statement protocol yet."
+const ER_UNSUPPORTED_PS: u16 = 1295;
+/// "Table '…' doesn't exist."
+const ER_NO_SUCH_TABLE: u16 = 1146;
+/// "Unknown table '…' in information_schema."
+const ER_UNKNOWN_TABLE: u16 = 1109;
+
+/// The server's error number (`1146`, …) behind `err`, if it came from one.
+fn error_number(err: &sqlx::Error) -> Option<u16> {
+    let db_err = err.as_database_error()?;
+    Some(db_err.try_downcast_ref::<MySqlDatabaseError>()?.number())
+}
+
+/// `KILL QUERY` on the connection `id`, if any.
+async fn kill_query(pool: &MySqlPool, id: Option<i64>) -> Result<bool> {
+    let Some(id) = id else {
+        return Ok(false);
+    };
+    // KILL does not take bind parameters; the id is a number we read ourselves.
+    pool.execute(format!("KILL QUERY {id}").as_str()).await?;
+    Ok(true)
+}
+
+/// Runs on its own pooled connection, recorded in `running` under
+/// `request` so that `cancel_count` can stop a long count.
+pub async fn count_rows(
+    pool: &MySqlPool,
+    running: &RunningQueries,
+    request: RequestId,
+    schema: &str,
+    table: &str,
+) -> Result<i64> {
+    let mut conn = pool.acquire().await?;
+    let id: u64 = sqlx::query_scalar("select connection_id()")
+        .fetch_one(&mut *conn)
+        .await?;
+    let _running = running.start(request, id as i64);
+
+    let sql = format!(
+        "select count(*) from {}.{}",
+        quote_ident(schema),
+        quote_ident(table)
+    );
+    Ok(sqlx::query_scalar(&sql).fetch_one(&mut *conn).await?)
+}
+
+/// Runs on the session connection, whose id is recorded in
+/// `running`, so `cancel` can `KILL QUERY` exactly this statement, and in
+/// `unread` until the whole result has arrived.
+pub fn execute_sql<'a>(
+    pool: &'a MySqlPool,
+    running: &'a RunningQuery,
+    unread: &'a RunningQuery,
+    session: &'a SessionConn<MySql>,
+    sql: &'a str,
+    params: &'a [Option<String>],
+) -> BoxStream<'a, Result<SqlStep>> {
+    let stream = try_stream! {
+        let mut session = session.get(pool).await?;
+        let conn: &mut MySqlConnection = &mut session;
+        let id: u64 = sqlx::query_scalar("select connection_id()")
+            .fetch_one(&mut *conn)
+            .await?;
+        let _running = running.start(id as i64);
+
+        // USE, LOCK TABLES and a few more cannot be prepared; their
+        // columns, if any, are taken from the first row instead.
+        let mut described = true;
+        let columns: Vec<ColumnMeta> = match describe::<MySql>(conn, sql).await {
+            Ok(statement) => statement.columns().iter().map(mysql_column_meta).collect(),
+            Err(e)
+                if e.downcast_ref().and_then(error_number) == Some(ER_UNSUPPORTED_PS) =>
+            {
+                described = false;
+                Vec::new()
+            }
+            Err(e) => Err(e)?,
+        };
+        if !columns.is_empty() {
+            yield SqlStep::Columns(columns);
+        }
+
+        // Without values to bind, the text protocol runs anything MySQL takes.
+        let mut steps = if params.is_empty() {
+            conn.fetch_many(sqlx::raw_sql(sql))
+        } else {
+            let mut query = sqlx::query(sql).persistent(false);
+            for value in params {
+                query = query.bind(value.as_deref());
+            }
+            conn.fetch_many(query)
+        };
+        unread.set(Some(id as i64));
+        while let Some(step) = steps.next().await {
+            let step = step.inspect_err(|_| unread.set(None))?;
+            if let Either::Right(row) = &step {
+                if !described {
+                    described = true;
+                    yield SqlStep::Columns(row.columns().iter().map(mysql_column_meta).collect());
+                }
+            }
+            yield sql_step(step.map_left(|res| res.rows_affected()), mysql_cell_value);
+        }
+        unread.set(None);
+    };
+    stream.boxed()
+}
+
+async fn schema_exists(pool: &MySqlPool, schema: &str) -> Result<bool> {
+    let exists: bool = sqlx::query_scalar(
+        r#"
+        select exists(
+            select 1
+            from information_schema.schemata
+            where schema_name = ?
+        )
+        "#,
+    )
+    .bind(schema)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
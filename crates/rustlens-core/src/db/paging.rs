@@ -0,0 +1,273 @@
+use sqlx::{ColumnIndex, Database, Decode, Row, Type};
+
+use crate::model::paging::{PageCursor, PageNav, PagePos};
+use crate::model::value::{CellValue, ColumnMeta};
+
+/// One column of the key a table is paged by.
+pub(crate) struct KeyColumn {
+    /// Quoted column name, as it appears after the `p.` alias.
+    pub ident: String,
+    /// The column read as text, e.g. `p."id"::text`.
+    pub text: String,
+    /// A bound text value converted back to the column's type, placeholder
+    /// included, e.g. `$1::integer`.
+    pub param: String,
+}
+
+pub(crate) struct KeysetQuery {
+    pub sql: String,
+    /// Key values to bind, in placeholder order.
+    pub binds: Vec<String>,
+    /// Rows come back in descending key order and must be reversed.
+    pub reverse: bool,
+    pub pos: PagePos,
+    pub page_size: i64,
+}
+
+/// Builds the query for the page `nav` points at. `table` is the quoted,
+/// schema-qualified name; the key's text columns come first in the select
+/// list, followed by `p.*`.
+pub(crate) fn keyset_query(
+    table: &str,
+    key: &[KeyColumn],
+    nav: PageNav,
+    cursor: &PageCursor,
+    page_size: i64,
+) -> KeysetQuery {
+    // Without an edge row to continue from there is nothing to page relative to.
+    let nav = match (nav, &cursor.first_key, &cursor.last_key) {
+        (PageNav::Next, _, None) | (PageNav::Prev, None, _) => PageNav::First,
+        (nav, _, _) => nav,
+    };
+
+    let (op, binds, reverse, pos) = match nav {
+        PageNav::First => (None, Vec::new(), false, PagePos::FromStart(0)),
+        PageNav::Last => (None, Vec::new(), true, PagePos::FromEnd(0)),
+        PageNav::Next => (
+            Some(">"),
+            cursor.last_key.clone().unwrap_or_default(),
+            false,
+            match cursor.pos {
+                PagePos::FromStart(i) => PagePos::FromStart(i + 1),
+                PagePos::FromEnd(i) => PagePos::FromEnd((i - 1).max(0)),
+            },
+        ),
+        PageNav::Prev => (
+            Some("<"),
+            cursor.first_key.clone().unwrap_or_default(),
+            true,
+            match cursor.pos {
+                PagePos::FromStart(i) => PagePos::FromStart((i - 1).max(0)),
+                PagePos::FromEnd(i) => PagePos::FromEnd(i + 1),
+            },
+        ),
+    };
+
+    let texts = key
+        .iter()
+        .enumerate()
+        .map(|(i, k)| format!("{} as __rustlens_k{i}", k.text))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let idents = key
+        .iter()
+        .map(|k| format!("p.{}", k.ident))
+        .collect::<Vec<_>>();
+
+    let params = key
+        .iter()
+        .map(|k| k.param.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut sql = format!("select {texts}, p.* from {table} p");
+    if let Some(op) = op {
+        sql += &format!(" where ({}) {op} ({})", idents.join(", "), params);
+    }
+    let dir = if reverse { " desc" } else { "" };
+    let order = idents
+        .iter()
+        .map(|i| format!("{i}{dir}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    sql += &format!(" order by {order} limit {page_size}");
+
+    KeysetQuery {
+        sql,
+        binds,
+        reverse,
+        pos,
+        page_size,
+    }
+}
+
+/// Splits the rows of a `keyset_query` into the table's own columns and
+/// cells, and the cursor for the page they make up.
+pub(crate) fn keyset_page<R: Row>(
+    mut rows: Vec<R>,
+    key_len: usize,
+    query: &KeysetQuery,
+    column_meta: fn(&<R::Database as Database>::Column) -> ColumnMeta,
+    cell_value: fn(&R, usize) -> CellValue,
+) -> (Vec<ColumnMeta>, Vec<Vec<CellValue>>, PageCursor)
+where
+    usize: ColumnIndex<R>,
+    for<'r> String: Decode<'r, R::Database> + Type<R::Database>,
+{
+    if query.reverse {
+        rows.reverse();
+    }
+
+    let columns: Vec<ColumnMeta> = rows
+        .first()
+        .map(|r| r.columns()[key_len..].iter().map(column_meta).collect())
+        .unwrap_or_default();
+
+    let key_of = |r: &R| -> Vec<String> {
+        (0..key_len)
+            .map(|i| r.try_get::<String, _>(i).unwrap_or_default())
+            .collect()
+    };
+    let cursor = PageCursor {
+        pos: query.pos,
+        first_key: rows.first().map(key_of),
+        last_key: rows.last().map(key_of),
+        at_end: query.pos == PagePos::FromEnd(0)
+            || (!query.reverse && (rows.len() as i64) < query.page_size),
+    };
+
+    let out = rows
+        .iter()
+        .map(|r| (key_len..r.len()).map(|i| cell_value(r, i)).collect())
+        .collect();
+
+    (columns, out, cursor)
+}
+
+/// 0-based page number for OFFSET paging. `rows` is only asked for when
+/// jumping to the last page.
+pub(crate) async fn offset_index<F>(
+    nav: PageNav,
+    cursor: &PageCursor,
+    page_size: i64,
+    rows: F,
+) -> anyhow::Result<i64>
+where
+    F: std::future::Future<Output = anyhow::Result<i64>>,
+{
+    Ok(match (nav, cursor.pos) {
+        (PageNav::First, _) => 0,
+        (PageNav::Last, _) => (rows.await? - 1).max(0) / page_size.max(1),
+        (PageNav::Next, PagePos::FromStart(i)) => i + 1,
+        (PageNav::Prev, PagePos::FromStart(i)) => (i - 1).max(0),
+        // Only keyset paging counts from the end; the key is gone, start over.
+        (_, PagePos::FromEnd(_)) => 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id_key() -> Vec<KeyColumn> {
+        vec![KeyColumn {
+            ident: "\"id\"".into(),
+            text: "p.\"id\"::text".into(),
+            param: "$1::integer".into(),
+        }]
+    }
+
+    fn cursor(pos: PagePos, first: &str, last: &str) -> PageCursor {
+        PageCursor {
+            pos,
+            first_key: Some(vec![first.to_string()]),
+            last_key: Some(vec![last.to_string()]),
+            at_end: false,
+        }
+    }
+
+    #[test]
+    fn first_and_last_pages_need_no_key() {
+        let q = keyset_query("t", &id_key(), PageNav::First, &PageCursor::default(), 50);
+        assert_eq!(
+            q.sql,
+            "select p.\"id\"::text as __rustlens_k0, p.* from t p order by p.\"id\" limit 50"
+        );
+        assert!(q.binds.is_empty() && !q.reverse);
+        assert_eq!(q.pos, PagePos::FromStart(0));
+
+        let q = keyset_query("t", &id_key(), PageNav::Last, &PageCursor::default(), 50);
+        assert!(q.sql.ends_with("order by p.\"id\" desc limit 50"));
+        assert!(q.reverse);
+        assert_eq!(q.pos, PagePos::FromEnd(0));
+    }
+
+    #[test]
+    fn continues_from_the_edge_rows() {
+        let on_screen = cursor(PagePos::FromStart(2), "101", "150");
+
+        let next = keyset_query("t", &id_key(), PageNav::Next, &on_screen, 50);
+        assert!(next
+            .sql
+            .contains(" where (p.\"id\") > ($1::integer) order by p.\"id\" limit 50"));
+        assert_eq!(next.binds, ["150"]);
+        assert_eq!(next.pos, PagePos::FromStart(3));
+
+        let prev = keyset_query("t", &id_key(), PageNav::Prev, &on_screen, 50);
+        assert!(prev
+            .sql
+            .contains(" where (p.\"id\") < ($1::integer) order by p.\"id\" desc limit 50"));
+        assert_eq!(prev.binds, ["101"]);
+        assert!(prev.reverse);
+        assert_eq!(prev.pos, PagePos::FromStart(1));
+    }
+
+    #[test]
+    fn counts_back_from_the_last_page() {
+        let last = cursor(PagePos::FromEnd(0), "951", "1000");
+        let prev = keyset_query("t", &id_key(), PageNav::Prev, &last, 50);
+        assert_eq!(prev.pos, PagePos::FromEnd(1));
+        let next = keyset_query("t", &id_key(), PageNav::Next, &last, 50);
+        assert_eq!(next.pos, PagePos::FromEnd(0));
+    }
+
+    #[test]
+    fn starts_over_without_edge_rows() {
+        let empty = PageCursor::default();
+        let q = keyset_query("t", &id_key(), PageNav::Next, &empty, 50);
+        assert!(q.binds.is_empty());
+        assert_eq!(q.pos, PagePos::FromStart(0));
+    }
+
+    #[tokio::test]
+    async fn offset_pages_count_only_for_the_last() {
+        let at = |i| PageCursor {
+            pos: PagePos::FromStart(i),
+            ..PageCursor::default()
+        };
+        let unused = async { panic!("counted rows") };
+        assert_eq!(
+            offset_index(PageNav::Next, &at(2), 50, unused)
+                .await
+                .unwrap(),
+            3
+        );
+        let unused = async { panic!("counted rows") };
+        assert_eq!(
+            offset_index(PageNav::Prev, &at(0), 50, unused)
+                .await
+                .unwrap(),
+            0
+        );
+        let rows = async { Ok(101) };
+        assert_eq!(
+            offset_index(PageNav::Last, &at(0), 50, rows).await.unwrap(),
+            2
+        );
+        let rows = async { Ok(0) };
+        assert_eq!(
+            offset_index(PageNav::Last, &at(0), 50, rows).await.unwrap(),
+            0
+        );
+    }
+}
@@ -1,20 +1,264 @@
-use crate::util::value_fmt::cell_to_string;
-use anyhow::anyhow;
-use anyhow::Result;
-use sqlx::{Column, PgPool, Row as _};
+use crate::db::backend::{
+    describe, sql_step, DatabaseBackend, RunningQueries, RunningQuery, SessionConn, SqlStep,
+};
+use crate::db::connect::open_pool;
+use crate::db::error::{DbError, SchemaNotFound};
+use crate::db::paging::{keyset_page, keyset_query, offset_index, KeyColumn};
+use crate::db::protocol::RequestId;
+use crate::model::catalog::{
+    Catalog, CheckConstraint, Column, ForeignKey, Identity, Index, PrimaryKey, Relation,
+    RelationKind, TableEntry, UniqueConstraint,
+};
+use crate::model::connection::{ConnectionOptions, Driver, SslMode};
+use crate::model::paging::{PageCursor, PageNav, PageOrder, PagePos, TablePage};
+use crate::model::value::ColumnMeta;
+use crate::util::decode::{pg_cell_value, pg_column_meta};
+use crate::util::statement::is_query;
+use anyhow::{Context, Result};
+use async_stream::try_stream;
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
+use sqlx::postgres::types::Oid;
+use sqlx::postgres::{PgConnectOptions, PgConnection, PgSslMode};
+use sqlx::{Either, Executor as _, PgPool, Postgres, Row as _, Statement as _};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex as SyncMutex;
 
-pub async fn load_tables(pool: &PgPool, schema: &str) -> Result<Vec<String>> {
+pub struct PostgresBackend {
+    pool: PgPool,
+    session: SessionConn<Postgres>,
+    /// The session connection while `execute_sql` runs on it.
+    running: RunningQuery,
+    /// The pooled connection of each `count_rows`.
+    counting: RunningQueries,
+    cursor: SyncMutex<CursorState>,
+}
+
+impl PostgresBackend {
+    pub async fn connect(database_url: &str, options: &ConnectionOptions) -> Result<Self> {
+        let opts = connect_options(database_url, options)?;
+        let pool = open_pool::<Postgres>(opts, options).await?;
+        Ok(Self {
+            pool,
+            running: RunningQuery::default(),
+            counting: RunningQueries::default(),
+            cursor: SyncMutex::default(),
+            session: SessionConn::default(),
+        })
+    }
+}
+
+/// The URL's settings, with the profile's socket, TLS files and mode laid
+/// over them. `?host=/var/run/postgresql` in the URL is a socket too.
+fn connect_options(database_url: &str, options: &ConnectionOptions) -> Result<PgConnectOptions> {
+    let mut opts = PgConnectOptions::from_str(database_url)
+        .context("invalid Postgres URL")?
+        .application_name(&options.application_name);
+    if let Some(dir) = &options.host {
+        opts = opts.socket(dir);
+    }
+    // No TLS over a Unix socket; asking for it would only fail.
+    if opts.get_socket().is_some() {
+        return Ok(opts.ssl_mode(PgSslMode::Disable));
+    }
+    if let Some(mode) = options.sslmode {
+        opts = opts.ssl_mode(match mode {
+            SslMode::Disable => PgSslMode::Disable,
+            SslMode::Allow => PgSslMode::Allow,
+            SslMode::Prefer => PgSslMode::Prefer,
+            SslMode::Require => PgSslMode::Require,
+            SslMode::VerifyCa => PgSslMode::VerifyCa,
+            SslMode::VerifyFull => PgSslMode::VerifyFull,
+        });
+    }
+    if let Some(path) = &options.sslrootcert {
+        opts = opts.ssl_root_cert(path);
+    }
+    if let Some(path) = &options.sslcert {
+        opts = opts.ssl_client_cert(path);
+    }
+    if let Some(path) = &options.sslkey {
+        opts = opts.ssl_client_key(path);
+    }
+    Ok(opts)
+}
+
+#[async_trait]
+impl DatabaseBackend for PostgresBackend {
+    fn driver(&self) -> Driver {
+        Driver::Postgres
+    }
+
+    async fn list_schemas(&self) -> Result<Vec<String>> {
+        load_schemas(&self.pool).await
+    }
+
+    async fn list_tables(&self, schema: &str) -> Result<Vec<TableEntry>> {
+        load_tables(&self.pool, schema).await
+    }
+
+    async fn load_catalog(&self, schema: &str) -> Result<Catalog> {
+        load_catalog(&self.pool, schema).await
+    }
+
+    async fn load_table_page(
+        &self,
+        schema: &str,
+        table: &str,
+        nav: PageNav,
+        cursor: &PageCursor,
+        page_size: i64,
+    ) -> Result<TablePage> {
+        load_table_page(&self.pool, schema, table, nav, cursor, page_size).await
+    }
+
+    async fn estimate_rows(&self, schema: &str, table: &str) -> Result<Option<i64>> {
+        estimate_rows(&self.pool, schema, table).await
+    }
+
+    async fn count_rows(&self, request: RequestId, schema: &str, table: &str) -> Result<i64> {
+        count_rows(&self.pool, &self.counting, request, schema, table).await
+    }
+
+    async fn refresh_materialized_view(&self, schema: &str, table: &str) -> Result<()> {
+        let sql = format!(
+            "refresh materialized view {}.{}",
+            quote_ident(schema),
+            quote_ident(table)
+        );
+        sqlx::query(&sql).execute(&self.pool).await?;
+        Ok(())
+    }
+
+    fn execute_sql<'a>(
+        &'a self,
+        sql: &'a str,
+        params: &'a [Option<String>],
+    ) -> BoxStream<'a, Result<SqlStep>> {
+        let conn = Session {
+            pool: &self.pool,
+            running: &self.running,
+            session: &self.session,
+            cursor: &self.cursor,
+        };
+        execute_sql(conn, sql, params)
+    }
+
+    async fn finish_sql(&self) -> Result<()> {
+        let Some(implicit) = self.cursor.lock().unwrap().open.take() else {
+            return Ok(());
+        };
+        if implicit {
+            // Read-only, so committing is as good as rolling back; it also
+            // ends the transaction if a FETCH failed in it.
+            self.session.execute(&self.pool, "COMMIT").await
+        } else {
+            // Fails only when the user's transaction is already aborted,
+            // and its rollback drops the cursor then.
+            let close = format!("close {CURSOR}");
+            let _ = self.session.execute(&self.pool, &close).await;
+            Ok(())
+        }
+    }
+
+    async fn describe_params(&self, sql: &str) -> Result<Vec<Option<String>>> {
+        describe_params(&self.pool, &self.session, sql).await
+    }
+
+    async fn cancel(&self) -> Result<bool> {
+        cancel_backend(&self.pool, self.running.get()).await
+    }
+
+    async fn cancel_count(&self, request: RequestId) -> Result<bool> {
+        cancel_backend(&self.pool, self.counting.get(request)).await
+    }
+
+    async fn begin(&self, sql: &str) -> Result<()> {
+        self.session.execute(&self.pool, sql).await?;
+        self.cursor.lock().unwrap().in_transaction = true;
+        Ok(())
+    }
+
+    async fn commit(&self) -> Result<()> {
+        self.cursor.lock().unwrap().in_transaction = false;
+        self.session.execute(&self.pool, "COMMIT").await
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        self.cursor.lock().unwrap().in_transaction = false;
+        self.session.execute(&self.pool, "ROLLBACK").await
+    }
+
+    async fn rollback_to(&self, savepoint: &str) -> Result<()> {
+        let sql = format!("ROLLBACK TO SAVEPOINT {savepoint}");
+        self.session.execute(&self.pool, &sql).await
+    }
+
+    async fn reset_session(&self) -> Result<()> {
+        *self.cursor.lock().unwrap() = CursorState::default();
+        self.session.reset().await
+    }
+}
+
+/// The cursor a query's result is read through, `FETCH_ROWS` at a time.
+const CURSOR: &str = "rustlens_cursor";
+const FETCH_ROWS: usize = 500;
+
+/// What `execute_sql` needs to know about the session's transaction.
+#[derive(Debug, Default)]
+struct CursorState {
+    /// Inside a transaction opened with `begin`.
+    in_transaction: bool,
+    /// Set while a query's cursor is open, until `finish_sql`;
+    /// `true` when the query began the transaction around it too.
+    open: Option<bool>,
+}
+
+/// The parts of the backend `execute_sql` runs on.
+struct Session<'a> {
+    pool: &'a PgPool,
+    running: &'a RunningQuery,
+    session: &'a SessionConn<Postgres>,
+    cursor: &'a SyncMutex<CursorState>,
+}
+
+pub async fn load_schemas(pool: &PgPool) -> Result<Vec<String>> {
+    let rows = sqlx::query(
+        r#"
+        select nspname
+        from pg_catalog.pg_namespace
+        where nspname not in ('pg_catalog', 'information_schema')
+          and nspname not like 'pg\_toast%'
+          and nspname not like 'pg\_temp\_%'
+        order by nspname
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| r.get::<String, _>("nspname"))
+        .collect())
+}
+
+pub async fn load_tables(pool: &PgPool, schema: &str) -> Result<Vec<TableEntry>> {
     let exists = schema_exists(pool, schema).await?;
 
     if !exists {
-        return Err(anyhow!(r#"schema "{}" does not exist"#, schema));
+        return Err(SchemaNotFound(schema.to_string()).into());
     }
+    // Partitions are left out; their parent pages through all of them.
     let rows = sqlx::query(
         r#"
-        select tablename
-        from pg_catalog.pg_tables
-        where schemaname = $1
-        order by tablename
+        select c.relname, c.relkind::text as relkind
+        from pg_catalog.pg_class c
+        join pg_catalog.pg_namespace n on n.oid = c.relnamespace
+        where n.nspname = $1
+          and c.relkind in ('r', 'v', 'm', 'p', 'f')
+          and not c.relispartition
+        order by c.relname
         "#,
     )
     .bind(schema)
@@ -23,7 +267,10 @@ pub async fn load_tables(pool: &PgPool, schema: &str) -> Result<Vec<String>> {
 
     Ok(rows
         .into_iter()
-        .map(|r| r.get::<String, _>("tablename"))
+        .map(|r| TableEntry {
+            name: r.get("relname"),
+            kind: RelationKind::from_relkind(&r.get::<String, _>("relkind")),
+        })
         .collect())
 }
 
@@ -31,114 +278,557 @@ fn quote_ident(s: &str) -> String {
     format!("\"{}\"", s.replace('"', "\"\""))
 }
 
-pub async fn load_columns(pool: &PgPool, schema: &str) -> Result<Vec<(String, Vec<String>)>> {
+/// Relations of `schema` with their columns, keys, indexes and checks.
+/// One query per kind of object, grouped by relation afterwards.
+pub async fn load_catalog(pool: &PgPool, schema: &str) -> Result<Catalog> {
+    if !schema_exists(pool, schema).await? {
+        return Err(SchemaNotFound(schema.to_string()).into());
+    }
+    let mut catalog = Catalog {
+        schemas: load_schemas(pool).await?,
+        schema: schema.to_string(),
+        relations: Vec::new(),
+    };
+
     let rows = sqlx::query(
         r#"
-        select table_name, column_name
-        from information_schema.columns
-        where table_schema = $1
-        order by table_name, ordinal_position
+        select c.relname, c.relkind::text as relkind,
+               pg_get_userbyid(c.relowner) as owner,
+               obj_description(c.oid, 'pg_class') as comment,
+               case when c.relkind in ('v', 'm') then pg_get_viewdef(c.oid, true) end as view_query,
+               case when c.relkind = 'p' then pg_get_partkeydef(c.oid) end as partition_key,
+               s.srvname as foreign_server
+        from pg_catalog.pg_class c
+        join pg_catalog.pg_namespace n on n.oid = c.relnamespace
+        left join pg_catalog.pg_foreign_table ft on ft.ftrelid = c.oid
+        left join pg_catalog.pg_foreign_server s on s.oid = ft.ftserver
+        where n.nspname = $1
+          and c.relkind in ('r', 'v', 'm', 'p', 'f')
+          and not c.relispartition
+        order by c.relname
         "#,
     )
     .bind(schema)
     .fetch_all(pool)
     .await?;
 
-    let mut out: Vec<(String, Vec<String>)> = Vec::new();
+    for r in rows {
+        let kind = RelationKind::from_relkind(&r.get::<String, _>("relkind"));
+        let mut rel = Relation::new(r.get("relname"), kind);
+        rel.owner = r.get("owner");
+        rel.comment = r.get("comment");
+        rel.view_query = r.get("view_query");
+        rel.partition_key = r.get("partition_key");
+        rel.foreign_server = r.get("foreign_server");
+        catalog.relations.push(rel);
+    }
+    let index: HashMap<String, usize> = catalog
+        .relations
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (r.name.clone(), i))
+        .collect();
+    let relation = |name: &str| index.get(name).copied();
+
+    let rows = sqlx::query(
+        r#"
+        select c.relname, a.attname,
+               format_type(a.atttypid, a.atttypmod) as data_type,
+               not a.attnotnull as nullable,
+               case when a.attgenerated = '' then pg_get_expr(d.adbin, d.adrelid) end as column_default,
+               case when a.attgenerated <> '' then pg_get_expr(d.adbin, d.adrelid) end as generated,
+               a.attidentity::text as identity,
+               col_description(a.attrelid, a.attnum) as comment
+        from pg_catalog.pg_attribute a
+        join pg_catalog.pg_class c on c.oid = a.attrelid
+        join pg_catalog.pg_namespace n on n.oid = c.relnamespace
+        left join pg_catalog.pg_attrdef d on d.adrelid = a.attrelid and d.adnum = a.attnum
+        where n.nspname = $1
+          and c.relkind in ('r', 'v', 'm', 'p', 'f')
+          and a.attnum > 0
+          and not a.attisdropped
+        order by c.relname, a.attnum
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
 
     for r in rows {
-        let t: String = r.get("table_name");
-        let c: String = r.get("column_name");
+        let Some(i) = relation(r.get("relname")) else {
+            continue;
+        };
+        catalog.relations[i].columns.push(Column {
+            name: r.get("attname"),
+            data_type: r.get("data_type"),
+            nullable: r.get("nullable"),
+            default: r.get("column_default"),
+            identity: match r.get::<String, _>("identity").as_str() {
+                "a" => Some(Identity::Always),
+                "d" => Some(Identity::ByDefault),
+                _ => None,
+            },
+            generated: r.get("generated"),
+            comment: r.get("comment"),
+        });
+    }
 
-        if out.last().map(|(tt, _)| tt.as_str()) == Some(t.as_str()) {
-            out.last_mut().unwrap().1.push(c);
-        } else {
-            out.push((t, vec![c]));
+    let rows = sqlx::query(
+        r#"
+        select c.relname, con.conname, con.contype::text as contype,
+               array(
+                   select a.attname::text
+                   from unnest(con.conkey) with ordinality as k(attnum, ord)
+                   join pg_catalog.pg_attribute a
+                     on a.attrelid = con.conrelid and a.attnum = k.attnum
+                   order by k.ord
+               ) as columns,
+               fn.nspname as ref_schema,
+               fc.relname as ref_table,
+               array(
+                   select a.attname::text
+                   from unnest(con.confkey) with ordinality as k(attnum, ord)
+                   join pg_catalog.pg_attribute a
+                     on a.attrelid = con.confrelid and a.attnum = k.attnum
+                   order by k.ord
+               ) as ref_columns,
+               con.confupdtype::text as on_update,
+               con.confdeltype::text as on_delete,
+               pg_get_expr(con.conbin, con.conrelid, true) as check_expr
+        from pg_catalog.pg_constraint con
+        join pg_catalog.pg_class c on c.oid = con.conrelid
+        join pg_catalog.pg_namespace n on n.oid = c.relnamespace
+        left join pg_catalog.pg_class fc on fc.oid = con.confrelid
+        left join pg_catalog.pg_namespace fn on fn.oid = fc.relnamespace
+        where n.nspname = $1
+          and con.contype in ('p', 'u', 'f', 'c')
+        order by c.relname, con.conname
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    for r in rows {
+        let Some(i) = relation(r.get("relname")) else {
+            continue;
+        };
+        let rel = &mut catalog.relations[i];
+        let name: String = r.get("conname");
+        match r.get::<String, _>("contype").as_str() {
+            "p" => {
+                rel.primary_key = Some(PrimaryKey {
+                    name: Some(name),
+                    columns: r.get("columns"),
+                })
+            }
+            "u" => rel.uniques.push(UniqueConstraint {
+                name: Some(name),
+                columns: r.get("columns"),
+            }),
+            "f" => rel.foreign_keys.push(ForeignKey {
+                name: Some(name),
+                columns: r.get("columns"),
+                ref_schema: r.get::<Option<String>, _>("ref_schema").unwrap_or_default(),
+                ref_table: r.get::<Option<String>, _>("ref_table").unwrap_or_default(),
+                ref_columns: r.get("ref_columns"),
+                on_update: fk_action(&r.get::<String, _>("on_update")),
+                on_delete: fk_action(&r.get::<String, _>("on_delete")),
+            }),
+            _ => rel.checks.push(CheckConstraint {
+                name: Some(name),
+                expression: r.get::<Option<String>, _>("check_expr").unwrap_or_default(),
+            }),
         }
     }
 
-    Ok(out)
+    let rows = sqlx::query(
+        r#"
+        select c.relname, i.relname as index_name, x.indisunique, x.indisprimary,
+               array(
+                   select pg_get_indexdef(x.indexrelid, k, true)
+                   from generate_series(1, x.indnkeyatts) as k
+                   order by k
+               ) as columns,
+               pg_get_expr(x.indpred, x.indrelid, true) as predicate,
+               pg_get_indexdef(x.indexrelid) as definition
+        from pg_catalog.pg_index x
+        join pg_catalog.pg_class c on c.oid = x.indrelid
+        join pg_catalog.pg_class i on i.oid = x.indexrelid
+        join pg_catalog.pg_namespace n on n.oid = c.relnamespace
+        where n.nspname = $1
+        order by c.relname, i.relname
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    for r in rows {
+        let Some(i) = relation(r.get("relname")) else {
+            continue;
+        };
+        catalog.relations[i].indexes.push(Index {
+            name: r.get("index_name"),
+            columns: r.get("columns"),
+            unique: r.get("indisunique"),
+            primary: r.get("indisprimary"),
+            predicate: r.get("predicate"),
+            definition: r.get("definition"),
+        });
+    }
+
+    Ok(catalog)
+}
+
+/// `pg_constraint.confupdtype` / `confdeltype` codes.
+fn fk_action(code: &str) -> String {
+    match code {
+        "r" => "RESTRICT",
+        "c" => "CASCADE",
+        "n" => "SET NULL",
+        "d" => "SET DEFAULT",
+        _ => "NO ACTION",
+    }
+    .to_string()
 }
 
 pub async fn load_table_page(
     pool: &PgPool,
     schema: &str,
     table: &str,
-    page: i64,
+    nav: PageNav,
+    cursor: &PageCursor,
     page_size: i64,
-) -> Result<(Vec<String>, Vec<Vec<String>>)> {
-    let offset = page * page_size;
+) -> Result<TablePage> {
+    let qualified = format!("{}.{}", quote_ident(schema), quote_ident(table));
+    let key = load_page_key(pool, schema, table).await?;
+
+    if key.is_empty() {
+        let count = format!("select count(*) from {qualified}");
+        let index = offset_index(nav, cursor, page_size, async {
+            Ok(sqlx::query_scalar::<_, i64>(&count).fetch_one(pool).await?)
+        })
+        .await?;
+
+        let order = physical_order(pool, schema, table).await?;
+        let sql = format!("select * from {qualified}{order} limit $1 offset $2");
+        let rows = sqlx::query(&sql)
+            .bind(page_size)
+            .bind(index * page_size)
+            .fetch_all(pool)
+            .await?;
+
+        let columns: Vec<ColumnMeta> = rows
+            .first()
+            .map(|r| r.columns().iter().map(pg_column_meta).collect())
+            .unwrap_or_default();
+        let at_end = nav == PageNav::Last || (rows.len() as i64) < page_size;
+        let rows = rows
+            .iter()
+            .map(|r| (0..columns.len()).map(|i| pg_cell_value(r, i)).collect())
+            .collect();
+
+        return Ok(TablePage {
+            columns,
+            rows,
+            order: PageOrder::Offset,
+            cursor: PageCursor {
+                pos: PagePos::FromStart(index),
+                at_end,
+                ..PageCursor::default()
+            },
+        });
+    }
+
+    let columns: Vec<KeyColumn> = key
+        .iter()
+        .enumerate()
+        .map(|(i, (name, type_name))| KeyColumn {
+            ident: quote_ident(name),
+            text: format!("p.{}::text", quote_ident(name)),
+            param: format!("${}::{type_name}", i + 1),
+        })
+        .collect();
+    let query = keyset_query(&qualified, &columns, nav, cursor, page_size);
+
+    let mut q = sqlx::query(&query.sql);
+    for v in &query.binds {
+        q = q.bind(v);
+    }
+    let rows = q.fetch_all(pool).await?;
+
+    let (columns, rows, cursor) =
+        keyset_page(rows, key.len(), &query, pg_column_meta, pg_cell_value);
+    Ok(TablePage {
+        columns,
+        rows,
+        order: PageOrder::Key(key.into_iter().map(|(name, _)| name).collect()),
+        cursor,
+    })
+}
+
+/// Columns (with their SQL types) of the key `load_table_page` orders by:
+/// the primary key, else the narrowest unique index over NOT NULL columns
+/// without expressions or a predicate. Empty if there is none.
+async fn load_page_key(pool: &PgPool, schema: &str, table: &str) -> Result<Vec<(String, String)>> {
+    let rows = sqlx::query(
+        r#"
+        with idx as (
+            select i.indexrelid, i.indrelid, i.indisprimary, i.indnkeyatts,
+                   i.indkey::int2[] as keys
+            from pg_catalog.pg_index i
+            join pg_catalog.pg_class c on c.oid = i.indrelid
+            join pg_catalog.pg_namespace n on n.oid = c.relnamespace
+            where n.nspname = $1
+              and c.relname = $2
+              and i.indisunique
+              and i.indisvalid
+              and i.indpred is null
+        ), cols as (
+            select idx.indexrelid, idx.indisprimary, idx.indnkeyatts, k.ord,
+                   a.attname, a.attnotnull,
+                   format_type(a.atttypid, a.atttypmod) as type_name
+            from idx
+            cross join lateral unnest(idx.keys) with ordinality as k(attnum, ord)
+            left join pg_catalog.pg_attribute a
+                   on a.attrelid = idx.indrelid and a.attnum = k.attnum
+            where k.ord <= idx.indnkeyatts
+        ), best as (
+            -- Expression columns (attnum 0) have no attribute and fail the check.
+            select indexrelid
+            from cols
+            group by indexrelid, indisprimary, indnkeyatts
+            having bool_and(attnotnull is true)
+            order by indisprimary desc, indnkeyatts, indexrelid
+            limit 1
+        )
+        select attname::text as attname, type_name
+        from cols
+        where indexrelid = (select indexrelid from best)
+        order by ord
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| (r.get("attname"), r.get("type_name")))
+        .collect())
+}
+
+/// `ORDER BY` for OFFSET paging: physical order where rows have one. Views
+/// and foreign tables have no `ctid` and are read in scan order.
+async fn physical_order(pool: &PgPool, schema: &str, table: &str) -> Result<&'static str> {
+    let kind: Option<String> = sqlx::query_scalar(
+        r#"
+        select c.relkind::text
+        from pg_catalog.pg_class c
+        join pg_catalog.pg_namespace n on n.oid = c.relnamespace
+        where n.nspname = $1
+          and c.relname = $2
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(match kind.as_deref() {
+        Some("r" | "m") => " order by ctid",
+        // The same ctid turns up in every partition.
+        Some("p") => " order by tableoid, ctid",
+        _ => "",
+    })
+}
+
+/// Scales `reltuples` from the last ANALYZE to the table's current size,
+/// the way the planner does, summed over the partitions of a partitioned
+/// table. `None` if it was never analyzed, and for views and foreign tables.
+pub async fn estimate_rows(pool: &PgPool, schema: &str, table: &str) -> Result<Option<i64>> {
+    let rows: Option<Option<f64>> = sqlx::query_scalar(
+        r#"
+        with rel as (
+            select c.oid, c.relkind
+            from pg_catalog.pg_class c
+            join pg_catalog.pg_namespace n on n.oid = c.relnamespace
+            where n.nspname = $1
+              and c.relname = $2
+        )
+        select sum(case
+                     when c.reltuples < 0 then null
+                     when c.relpages = 0 then c.reltuples::float8
+                     else c.reltuples / c.relpages
+                          * (pg_relation_size(c.oid) / current_setting('block_size')::int)
+                   end)
+        from pg_catalog.pg_class c
+        where c.oid in (
+            select oid from rel where relkind in ('r', 'm')
+            union all
+            select t.relid
+            from rel, pg_partition_tree(rel.oid) t
+            where rel.relkind = 'p' and t.isleaf
+        )
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(rows.flatten().map(|n| n.round() as i64))
+}
+
+/// Signals the backend `pid`, if any, to stop its statement.
+async fn cancel_backend(pool: &PgPool, pid: Option<i64>) -> Result<bool> {
+    let Some(pid) = pid else {
+        return Ok(false);
+    };
+    let cancelled: bool = sqlx::query_scalar("select pg_cancel_backend($1)")
+        .bind(pid as i32)
+        .fetch_one(pool)
+        .await?;
+    Ok(cancelled)
+}
+
+/// Runs on its own pooled connection, recorded in `running` under
+/// `request` so that `cancel_count` can stop a long count.
+pub async fn count_rows(
+    pool: &PgPool,
+    running: &RunningQueries,
+    request: RequestId,
+    schema: &str,
+    table: &str,
+) -> Result<i64> {
+    let mut conn = pool.acquire().await?;
+    let pid: i32 = sqlx::query_scalar("select pg_backend_pid()")
+        .fetch_one(&mut *conn)
+        .await?;
+    let _running = running.start(request, pid.into());
+
     let sql = format!(
-        "select * from {}.{} limit $1 offset $2",
+        "select count(*) from {}.{}",
         quote_ident(schema),
-        quote_ident(table),
+        quote_ident(table)
     );
+    Ok(sqlx::query_scalar(&sql).fetch_one(&mut *conn).await?)
+}
 
-    let rows = sqlx::query(&sql)
-        .bind(page_size)
-        .bind(offset)
-        .fetch_all(pool)
-        .await?;
-
-    let columns: Vec<String> = rows
-        .get(0)
-        .map(|r| r.columns().iter().map(|c| c.name().to_string()).collect())
-        .unwrap_or_else(|| vec![]);
+/// Runs on the session connection, whose backend pid is recorded
+/// in `running`, so `cancel` can signal exactly this statement.
+///
+/// A query is read through a cursor, `FETCH_ROWS` at a time, so the server
+/// sends no more than the caller reads; outside a transaction, one is begun
+/// for it. `finish_sql` closes the cursor.
+fn execute_sql<'a>(
+    s: Session<'a>,
+    sql: &'a str,
+    params: &'a [Option<String>],
+) -> BoxStream<'a, Result<SqlStep>> {
+    let stream = try_stream! {
+        let mut session = s.session.get(s.pool).await?;
+        let conn: &mut PgConnection = &mut session;
+        let pid: i32 = sqlx::query_scalar("select pg_backend_pid()")
+            .fetch_one(&mut *conn)
+            .await?;
+        let _running = s.running.start(pid.into());
 
-    // Values (generic display for MVP phase)
-    let mut out = Vec::with_capacity(rows.len());
-    for r in rows {
-        let mut vals = Vec::with_capacity(columns.len());
-        for i in 0..columns.len() {
-            vals.push(cell_to_string(&r, i));
+        let columns: Vec<ColumnMeta> = describe::<Postgres>(conn, sql)
+            .await?
+            .columns()
+            .iter()
+            .map(pg_column_meta)
+            .collect();
+        if !columns.is_empty() {
+            yield SqlStep::Columns(columns.clone());
         }
-        out.push(vals);
-    }
-
-    Ok((columns, out))
-}
-
-pub enum SqlExecResult {
-    Rows {
-        columns: Vec<String>,
-        rows: Vec<Vec<String>>,
-    },
-    Command {
-        info: String,
-    },
-}
-
-pub async fn execute_sql(pool: &PgPool, sql: &str) -> Result<SqlExecResult> {
-    // MVP strategy:
-    // Try fetch_all -> if it fails due to "no rows returned" then fall back to execute.
-    // sqlx doesn't give a universal "is this SELECT" without parsing; this is simple and works.
-    // Should be refactored, this is only concept for development
-    match sqlx::query(sql).fetch_all(pool).await {
-        Ok(rows) => {
-            let columns: Vec<String> = rows
-                .get(0)
-                .map(|r| r.columns().iter().map(|c| c.name().to_string()).collect())
-                .unwrap_or_else(|| vec![]);
-
-            let mut out = Vec::with_capacity(rows.len());
-            for r in rows {
-                let mut vals = Vec::with_capacity(columns.len());
-                for i in 0..columns.len() {
-                    vals.push(cell_to_string(&r, i));
-                }
-                out.push(vals);
+
+        if columns.is_empty() || !is_query(sql, Driver::Postgres) {
+            let mut query = sqlx::query(sql).persistent(false);
+            for value in params {
+                query = query.bind(value.as_deref());
             }
+            let mut steps = conn.fetch_many(query);
+            while let Some(step) = steps.next().await {
+                yield sql_step(step?.map_left(|res| res.rows_affected()), pg_cell_value);
+            }
+            return;
+        }
 
-            Ok(SqlExecResult::Rows { columns, rows: out })
+        let implicit = !s.cursor.lock().unwrap().in_transaction;
+        if implicit {
+            conn.execute("begin").await?;
         }
-        Err(_) => {
-            let res = sqlx::query(sql).execute(pool).await?;
-            Ok(SqlExecResult::Command {
-                info: format!("OK. {} rows affected.", res.rows_affected()),
-            })
+        // Recorded before DECLARE, so a failing one still has its
+        // transaction ended by `finish_sql`.
+        s.cursor.lock().unwrap().open = Some(implicit);
+
+        let prefix = format!("declare {CURSOR} no scroll cursor for ");
+        let declare = format!("{prefix}{sql}");
+        let mut query = sqlx::query(&declare).persistent(false);
+        for value in params {
+            query = query.bind(value.as_deref());
+        }
+        if let Err(e) = conn.execute(query).await {
+            // Point into `sql`, not the DECLARE around it.
+            let mut err = DbError::from_anyhow(&e.into());
+            err.position = err.position.map(|p| p.saturating_sub(prefix.len()).max(1));
+            Err(err)?;
+        }
+
+        let fetch = format!("fetch forward {FETCH_ROWS} from {CURSOR}");
+        let mut total = 0;
+        loop {
+            let mut fetched = 0;
+            // Unnamed, so each cursor's FETCH is described afresh.
+            let mut rows = conn.fetch(sqlx::query(&fetch).persistent(false));
+            while let Some(row) = rows.next().await {
+                fetched += 1;
+                yield sql_step(Either::Right(row?), pg_cell_value);
+            }
+            total += fetched;
+            if fetched < FETCH_ROWS {
+                break;
+            }
         }
+        yield SqlStep::Done { rows_affected: total as u64 };
+    };
+    stream.boxed()
+}
+
+/// Prepares `sql` on the session connection, where its temp tables are
+/// visible, and spells each parameter's inferred type as SQL would.
+async fn describe_params(
+    pool: &PgPool,
+    session: &SessionConn<Postgres>,
+    sql: &str,
+) -> Result<Vec<Option<String>>> {
+    let mut session = session.get(pool).await?;
+    let conn: &mut PgConnection = &mut session;
+    let oids: Vec<Option<Oid>> = match describe::<Postgres>(conn, sql).await?.parameters() {
+        Some(Either::Left(types)) => types.iter().map(|t| t.oid()).collect(),
+        _ => Vec::new(),
+    };
+
+    let mut types = Vec::with_capacity(oids.len());
+    for oid in oids {
+        let name = match oid {
+            Some(oid) => Some(
+                sqlx::query_scalar("select format_type($1, null)")
+                    .bind(oid)
+                    .fetch_one(&mut *conn)
+                    .await?,
+            ),
+            None => None,
+        };
+        types.push(name);
     }
+    Ok(types)
 }
+
 async fn schema_exists(pool: &PgPool, schema: &str) -> Result<bool> {
     let exists: bool = sqlx::query_scalar(
         r#"
@@ -1,47 +1,269 @@
+use crate::db::error::DbError;
+use crate::model::catalog::{Catalog, TableEntry};
+use crate::model::connection::{ConnectionOptions, Driver};
+use crate::model::paging::{PageCursor, PageNav, PageOrder, RowCount};
+use crate::model::plan::Plan;
+use crate::model::value::{CellValue, ColumnMeta, QueryParam};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Identifies one request; every event answering it carries the same id.
+/// Ids grow with each request, so a higher one is a newer request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RequestId(pub u64);
+
+/// The part of the UI a request feeds: the Browse tab or the SQL tab.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Browse,
+    Sql,
+}
+
+/// A command on its way to the worker.
+#[derive(Debug)]
+pub struct DbRequest {
+    pub id: RequestId,
+    pub pane: Pane,
+    pub cmd: DbCmd,
+}
+
+/// An event on its way back, tagged with the request it answers.
+#[derive(Debug)]
+pub struct DbReply {
+    pub id: RequestId,
+    pub pane: Pane,
+    pub evt: DbEvt,
+}
+
+/// The UI's end of the command channel. Gives each command a fresh id,
+/// which the caller keeps to tell current answers from superseded ones.
+#[derive(Debug)]
+pub struct DbClient {
+    tx: mpsc::Sender<DbRequest>,
+    next_id: AtomicU64,
+}
+
+impl DbClient {
+    pub fn new(tx: mpsc::Sender<DbRequest>) -> Self {
+        Self {
+            tx,
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Queues `cmd` for `pane` and returns the id its events will carry.
+    /// A worker that has gone away is not an error here; nothing will answer.
+    pub async fn send(&self, pane: Pane, cmd: DbCmd) -> RequestId {
+        let id = RequestId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let _ = self.tx.send(DbRequest { id, pane, cmd }).await;
+        id
+    }
+}
+
 #[derive(Debug)]
 pub enum DbCmd {
     Connect {
+        driver: Driver,
         database_url: String,
+        options: ConnectionOptions,
     },
+    /// List the schemas on the connection; answered with `SchemasLoaded`.
+    LoadSchemas,
     LoadTables {
         schema: String,
     },
     LoadTablePage {
         schema: String,
         table: String,
-        page: i64,
+        nav: PageNav,
+        /// The page on screen; `PageCursor::default()` when opening a table.
+        cursor: PageCursor,
         page_size: i64,
     },
+    /// Exact `count(*)` of a table; stopped by `Cancel`.
+    CountRows {
+        schema: String,
+        table: String,
+    },
+    /// Answered with `MaterializedViewRefreshed`.
+    RefreshMaterializedView {
+        schema: String,
+        table: String,
+    },
+    /// Rebuild the statements that create `table`; answered with `Ddl`.
+    LoadDdl {
+        schema: String,
+        table: String,
+    },
+    /// Runs a script of one or more `;`-separated statements.
     ExecuteSql {
         sql: String,
+        /// Stop reading rows once the buffered result reaches this many bytes.
+        max_bytes: usize,
+        /// Skip the remaining statements after one fails.
+        stop_on_error: bool,
+        /// Values for the script's `$1` / `:name` placeholders.
+        params: Vec<QueryParam>,
+    },
+    /// Find the placeholders of a script and, where the driver can tell,
+    /// their types; answered with `ParamsDescribed`.
+    DescribeParams {
+        sql: String,
     },
+    /// `EXPLAIN (FORMAT JSON)` one statement on the session connection;
+    /// answered with `PlanLoaded`. With `analyze` the statement really runs.
+    /// Stopped by `Cancel`.
+    Explain {
+        sql: String,
+        analyze: bool,
+        buffers: bool,
+    },
+    /// Load the schema's catalog; answered with `SqlMetaLoaded`.
     LoadSqlMeta {
         schema: String,
     },
+    /// Stop what runs for the request's pane: the `ExecuteSql` or `Explain`
+    /// of the SQL tab, the `CountRows` of Browse.
+    Cancel,
+
+    /// Open a transaction on the session connection `ExecuteSql` runs on.
+    Begin,
+    Commit,
+    Rollback,
+    /// With autocommit off, the first statement outside a transaction opens one.
+    SetAutocommit(bool),
+    /// Close the session connection and start over on a fresh one,
+    /// dropping its settings, temp tables and any open transaction.
+    ResetSession,
+}
+
+impl DbCmd {
+    /// Runs on the SQL tab's session connection, in order with the other
+    /// such commands. The rest run on the pool, side by side.
+    pub fn on_session(&self) -> bool {
+        matches!(
+            self,
+            DbCmd::ExecuteSql { .. }
+                | DbCmd::DescribeParams { .. }
+                | DbCmd::Explain { .. }
+                | DbCmd::Begin
+                | DbCmd::Commit
+                | DbCmd::Rollback
+                | DbCmd::SetAutocommit(_)
+                | DbCmd::ResetSession
+        )
+    }
+}
+
+/// Transaction state of the SQL session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TxStatus {
+    #[default]
+    Idle,
+    Active,
+    /// A statement failed inside the transaction (Postgres); only a rollback helps now.
+    Failed,
 }
 
 #[derive(Debug)]
 pub enum DbEvt {
     Status(String),
-    Error(String),
+    /// The command failed; the worker stays up for the next one.
+    Error(DbError),
+
+    TxStatus(TxStatus),
+    /// An explicit `Begin`, `Commit` or `Rollback` succeeded; `tag` says which
+    /// (a COMMIT of a failed transaction reports `ROLLBACK`).
+    TxDone {
+        tag: String,
+    },
 
+    Ddl {
+        table: String,
+        sql: String,
+    },
+    SchemasLoaded {
+        schemas: Vec<String>,
+    },
     TablesLoaded {
-        tables: Vec<String>,
+        tables: Vec<TableEntry>,
+    },
+
+    /// Sent after the first page of a table (estimated) and for `CountRows` (exact).
+    RowCount {
+        schema: String,
+        table: String,
+        count: RowCount,
+    },
+    CountCancelled,
+    MaterializedViewRefreshed {
+        schema: String,
+        table: String,
     },
 
     QueryResult {
-        columns: Vec<String>,
-        rows: Vec<Vec<String>>,
+        columns: Vec<ColumnMeta>,
+        rows: Vec<Vec<CellValue>>,
+        order: PageOrder,
+        cursor: PageCursor,
         info: String,
     },
 
-    SqlExecuted {
-        info: String,
+    /// A batch of rows from a streamed query.
+    /// `columns` is set on the first batch only; it starts a new result.
+    RowBatch {
+        columns: Option<Vec<ColumnMeta>>,
+        rows: Vec<Vec<CellValue>>,
+    },
+
+    /// One statement of an `ExecuteSql` script finished.
+    StatementDone {
+        index: usize,
+        count: usize,
+        sql: String,
+        outcome: StatementOutcome,
+        elapsed: Duration,
     },
 
+    /// The whole `ExecuteSql` script finished (or stopped).
+    ScriptDone {
+        executed: usize,
+        count: usize,
+        failed: usize,
+        elapsed: Duration,
+    },
+
+    /// The placeholders of `sql`, each once, with no values yet.
+    ParamsDescribed {
+        sql: String,
+        params: Vec<QueryParam>,
+    },
+
+    PlanLoaded {
+        plan: Box<Plan>,
+    },
+    ExplainCancelled,
+
+    /// Everything completion, browsing and editing need to know about the schema.
     SqlMetaLoaded {
-        schema: String,
-        tables: Vec<String>,
-        columns: Vec<(String, Vec<String>)>,
+        catalog: Catalog,
+    },
+}
+
+/// How one statement of a script ended.
+#[derive(Debug, Clone)]
+pub enum StatementOutcome {
+    /// Returned a result set, delivered through `RowBatch`.
+    Rows {
+        total: usize,
+        /// The memory cap was hit before the server ran out of rows.
+        more_available: bool,
     },
+    /// No result set; `tag` is the command tag, e.g. `INSERT 0 5`.
+    Command { tag: String },
+    /// `position`, if any, counts from the start of the script.
+    Failed(DbError),
+    /// Stopped by `DbCmd::Cancel`; rows already delivered are kept.
+    Cancelled { rows: usize },
 }
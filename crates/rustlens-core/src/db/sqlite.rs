@@ -0,0 +1,504 @@
+use crate::db::backend::{describe, sql_step, DatabaseBackend, SessionConn, SqlStep};
+use crate::db::error::SchemaNotFound;
+use crate::db::paging::{keyset_page, keyset_query, offset_index, KeyColumn};
+use crate::db::protocol::RequestId;
+use crate::model::catalog::{
+    Catalog, Column, ForeignKey, Identity, Index, PrimaryKey, Relation, RelationKind, TableEntry,
+    UniqueConstraint,
+};
+use crate::model::connection::{ConnectionOptions, Driver};
+use crate::model::paging::{PageCursor, PageNav, PageOrder, PagePos, TablePage};
+use crate::model::value::ColumnMeta;
+use crate::util::decode::{sqlite_cell_value, sqlite_column_meta};
+use anyhow::{bail, Context, Result};
+use async_stream::try_stream;
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqlitePool, SqlitePoolOptions};
+use sqlx::Sqlite;
+use sqlx::{Executor as _, Row as _, Statement as _};
+use std::str::FromStr;
+
+pub struct SqliteBackend {
+    pool: SqlitePool,
+    session: SessionConn<Sqlite>,
+}
+
+impl SqliteBackend {
+    /// Accepts `sqlite://path/to.db`, `sqlite::memory:` or a bare file path.
+    /// Of `options` only the timeouts apply; a file has no TLS, and one
+    /// pooled connection is all it needs.
+    pub async fn connect(database_url: &str, options: &ConnectionOptions) -> Result<Self> {
+        if options.ssh.is_some() {
+            bail!("SQLite files cannot be opened through an SSH tunnel.");
+        }
+        let opts = if database_url.to_ascii_lowercase().starts_with("sqlite:") {
+            SqliteConnectOptions::from_str(database_url)?
+        } else {
+            SqliteConnectOptions::new().filename(database_url)
+        };
+
+        // The SQL tab opens its own connection from these options.
+        // `sqlite::memory:` URLs use a shared cache, so it sees the same database.
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(options.connect_timeout())
+            .idle_timeout(options.idle_timeout())
+            .connect_with(opts)
+            .await
+            .context("failed to open sqlite database")?;
+        Ok(Self {
+            pool,
+            session: SessionConn::default(),
+        })
+    }
+}
+
+#[async_trait]
+impl DatabaseBackend for SqliteBackend {
+    fn driver(&self) -> Driver {
+        Driver::Sqlite
+    }
+
+    async fn list_schemas(&self) -> Result<Vec<String>> {
+        load_schemas(&self.pool).await
+    }
+
+    async fn list_tables(&self, schema: &str) -> Result<Vec<TableEntry>> {
+        load_tables(&self.pool, schema).await
+    }
+
+    async fn load_catalog(&self, schema: &str) -> Result<Catalog> {
+        load_catalog(&self.pool, schema).await
+    }
+
+    async fn load_table_page(
+        &self,
+        schema: &str,
+        table: &str,
+        nav: PageNav,
+        cursor: &PageCursor,
+        page_size: i64,
+    ) -> Result<TablePage> {
+        load_table_page(&self.pool, schema, table, nav, cursor, page_size).await
+    }
+
+    async fn count_rows(&self, _request: RequestId, schema: &str, table: &str) -> Result<i64> {
+        count_rows(&self.pool, schema, table).await
+    }
+
+    fn execute_sql<'a>(
+        &'a self,
+        sql: &'a str,
+        params: &'a [Option<String>],
+    ) -> BoxStream<'a, Result<SqlStep>> {
+        execute_sql(&self.pool, &self.session, sql, params)
+    }
+
+    async fn begin(&self, sql: &str) -> Result<()> {
+        self.session.execute(&self.pool, sql).await
+    }
+
+    async fn commit(&self) -> Result<()> {
+        self.session.execute(&self.pool, "COMMIT").await
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        self.session.execute(&self.pool, "ROLLBACK").await
+    }
+
+    async fn rollback_to(&self, savepoint: &str) -> Result<()> {
+        let sql = format!("ROLLBACK TO SAVEPOINT {savepoint}");
+        self.session.execute(&self.pool, &sql).await
+    }
+
+    async fn reset_session(&self) -> Result<()> {
+        self.session.reset().await
+    }
+}
+
+/// Attached databases (`main`, `temp`, anything from ATTACH) play the role of schemas.
+pub async fn load_schemas(pool: &SqlitePool) -> Result<Vec<String>> {
+    let rows = sqlx::query("pragma database_list").fetch_all(pool).await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| r.get::<String, _>("name"))
+        .collect())
+}
+
+pub async fn load_tables(pool: &SqlitePool, schema: &str) -> Result<Vec<TableEntry>> {
+    let exists = load_schemas(pool).await?.iter().any(|s| s == schema);
+
+    if !exists {
+        return Err(SchemaNotFound(schema.to_string()).into());
+    }
+    let sql = format!(
+        r#"
+        select name, type
+        from {}.sqlite_master
+        where type in ('table', 'view')
+          and name not like 'sqlite\_%' escape '\'
+        order by name
+        "#,
+        quote_ident(schema),
+    );
+    let rows = sqlx::query(&sql).fetch_all(pool).await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| TableEntry {
+            name: r.get("name"),
+            kind: master_kind(&r.get::<String, _>("type")),
+        })
+        .collect())
+}
+
+/// `sqlite_master.type` of a table or view.
+fn master_kind(kind: &str) -> RelationKind {
+    match kind {
+        "view" => RelationKind::View,
+        _ => RelationKind::Table,
+    }
+}
+
+fn quote_ident(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+/// Tables and views of `schema` with their columns, keys and indexes.
+/// SQLite keeps no CHECK constraints outside the `CREATE TABLE` text, so
+/// `checks` stays empty.
+pub async fn load_catalog(pool: &SqlitePool, schema: &str) -> Result<Catalog> {
+    let schemas = load_schemas(pool).await?;
+
+    if !schemas.iter().any(|s| s == schema) {
+        return Err(SchemaNotFound(schema.to_string()).into());
+    }
+    let sql = format!(
+        r#"
+        select name, type, sql
+        from {}.sqlite_master
+        where type in ('table', 'view')
+          and name not like 'sqlite\_%' escape '\'
+        order by name
+        "#,
+        quote_ident(schema),
+    );
+    let rows = sqlx::query(&sql).fetch_all(pool).await?;
+
+    let mut relations = Vec::with_capacity(rows.len());
+    for r in rows {
+        let kind = master_kind(&r.get::<String, _>("type"));
+        let mut rel = Relation::new(r.get("name"), kind);
+        if kind == RelationKind::View {
+            rel.view_query = r
+                .get::<Option<String>, _>("sql")
+                .as_deref()
+                .and_then(view_query);
+        }
+        load_relation(pool, schema, &mut rel).await?;
+        relations.push(rel);
+    }
+
+    Ok(Catalog {
+        schemas,
+        schema: schema.to_string(),
+        relations,
+    })
+}
+
+async fn load_relation(pool: &SqlitePool, schema: &str, rel: &mut Relation) -> Result<()> {
+    let rows = sqlx::query(
+        "select name, type, \"notnull\", dflt_value, pk, hidden from pragma_table_xinfo(?1, ?2) order by cid",
+    )
+    .bind(&rel.name)
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    let mut pk: Vec<(i64, String)> = Vec::new();
+    for r in rows {
+        let name: String = r.get("name");
+        let hidden: i64 = r.get("hidden");
+        // 1 marks the hidden columns of a virtual table.
+        if hidden == 1 {
+            continue;
+        }
+        if r.get::<i64, _>("pk") > 0 {
+            pk.push((r.get("pk"), name.clone()));
+        }
+        rel.columns.push(Column {
+            name,
+            data_type: r.get("type"),
+            nullable: r.get::<i64, _>("notnull") == 0,
+            default: r.get("dflt_value"),
+            identity: None,
+            // 2 and 3 are virtual and stored generated columns.
+            generated: (hidden >= 2).then(String::new),
+            comment: None,
+        });
+    }
+    pk.sort();
+
+    if let [(_, name)] = pk.as_slice() {
+        // A lone INTEGER PRIMARY KEY is the rowid and numbers itself.
+        if let Some(c) = rel.columns.iter_mut().find(|c| &c.name == name) {
+            if c.data_type.eq_ignore_ascii_case("integer") {
+                c.identity = Some(Identity::ByDefault);
+                c.nullable = false;
+            }
+        }
+    }
+    if !pk.is_empty() {
+        rel.primary_key = Some(PrimaryKey {
+            name: None,
+            columns: pk.into_iter().map(|(_, name)| name).collect(),
+        });
+    }
+
+    let rows = sqlx::query(
+        r#"select id, "table", "from", "to", on_update, on_delete
+           from pragma_foreign_key_list(?1, ?2)
+           order by id, seq"#,
+    )
+    .bind(&rel.name)
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    let mut last_id = None;
+    for r in rows {
+        let id: i64 = r.get("id");
+        let column: String = r.get("from");
+        let ref_column: Option<String> = r.get("to");
+
+        match rel.foreign_keys.last_mut() {
+            Some(fk) if last_id == Some(id) => {
+                fk.columns.push(column);
+                fk.ref_columns.extend(ref_column);
+            }
+            _ => rel.foreign_keys.push(ForeignKey {
+                name: None,
+                columns: vec![column],
+                ref_schema: schema.to_string(),
+                ref_table: r.get("table"),
+                ref_columns: ref_column.into_iter().collect(),
+                on_update: r.get("on_update"),
+                on_delete: r.get("on_delete"),
+            }),
+        }
+        last_id = Some(id);
+    }
+
+    let sql = format!(
+        r#"
+        select l.name, l."unique", l.origin, l.partial, m.sql
+        from pragma_index_list(?1, ?2) l
+        left join {}.sqlite_master m on m.type = 'index' and m.name = l.name
+        order by l.name
+        "#,
+        quote_ident(schema),
+    );
+    let rows = sqlx::query(&sql)
+        .bind(&rel.name)
+        .bind(schema)
+        .fetch_all(pool)
+        .await?;
+
+    for r in rows {
+        let name: String = r.get("name");
+        // Expression parts have no column name.
+        let columns: Vec<String> = sqlx::query_scalar(
+            "select coalesce(name, '(expression)') from pragma_index_info(?1, ?2) order by seqno",
+        )
+        .bind(&name)
+        .bind(schema)
+        .fetch_all(pool)
+        .await?;
+        // Indexes SQLite made for a constraint have no statement.
+        let definition: Option<String> = r.get("sql");
+        let origin: String = r.get("origin");
+        if origin == "u" {
+            rel.uniques.push(UniqueConstraint {
+                name: None,
+                columns: columns.clone(),
+            });
+        }
+        let predicate = if r.get::<i64, _>("partial") != 0 {
+            definition.as_deref().and_then(index_predicate)
+        } else {
+            None
+        };
+
+        rel.indexes.push(Index {
+            name,
+            columns,
+            unique: r.get::<i64, _>("unique") != 0,
+            primary: origin == "pk",
+            predicate,
+            definition,
+        });
+    }
+
+    Ok(())
+}
+
+/// The `SELECT` of a `CREATE VIEW` statement: whatever follows the first `AS`.
+fn view_query(sql: &str) -> Option<String> {
+    let lower = sql.to_ascii_lowercase();
+    let at = lower.match_indices("as").map(|(i, _)| i).find(|&i| {
+        let before = lower[..i].chars().last();
+        let after = lower[i + 2..].chars().next();
+        before.is_some_and(char::is_whitespace) && after.is_some_and(char::is_whitespace)
+    })?;
+    Some(sql[at + 2..].trim().to_string())
+}
+
+/// The condition after the last `WHERE` of a `CREATE INDEX` statement.
+fn index_predicate(sql: &str) -> Option<String> {
+    let at = sql.to_ascii_lowercase().rfind("where")?;
+    Some(sql[at + "where".len()..].trim().to_string())
+}
+
+pub async fn load_table_page(
+    pool: &SqlitePool,
+    schema: &str,
+    table: &str,
+    nav: PageNav,
+    cursor: &PageCursor,
+    page_size: i64,
+) -> Result<TablePage> {
+    let qualified = format!("{}.{}", quote_ident(schema), quote_ident(table));
+    let sql = format!(
+        "select type from {}.sqlite_master where name = ?",
+        quote_ident(schema)
+    );
+    let kind: Option<String> = sqlx::query_scalar(&sql)
+        .bind(table)
+        .fetch_optional(pool)
+        .await?;
+
+    if kind.as_deref() == Some("view") {
+        // Views have no rowid; pages follow the view's own order.
+        let count = format!("select count(*) from {qualified}");
+        let index = offset_index(nav, cursor, page_size, async {
+            Ok(sqlx::query_scalar::<_, i64>(&count).fetch_one(pool).await?)
+        })
+        .await?;
+
+        let sql = format!("select * from {qualified} limit ? offset ?");
+        let rows = sqlx::query(&sql)
+            .bind(page_size)
+            .bind(index * page_size)
+            .fetch_all(pool)
+            .await?;
+
+        let columns: Vec<ColumnMeta> = rows
+            .first()
+            .map(|r| r.columns().iter().map(sqlite_column_meta).collect())
+            .unwrap_or_default();
+        let at_end = nav == PageNav::Last || (rows.len() as i64) < page_size;
+        let rows = rows
+            .iter()
+            .map(|r| {
+                (0..columns.len())
+                    .map(|i| sqlite_cell_value(r, i))
+                    .collect()
+            })
+            .collect();
+
+        return Ok(TablePage {
+            columns,
+            rows,
+            order: PageOrder::Offset,
+            cursor: PageCursor {
+                pos: PagePos::FromStart(index),
+                at_end,
+                ..PageCursor::default()
+            },
+        });
+    }
+
+    let mut key: Vec<String> =
+        sqlx::query_scalar("select name from pragma_table_info(?1, ?2) where pk > 0 order by pk")
+            .bind(table)
+            .bind(schema)
+            .fetch_all(pool)
+            .await?;
+    if key.is_empty() {
+        key.push("rowid".to_string());
+    }
+
+    let columns: Vec<KeyColumn> = key
+        .iter()
+        .map(|name| KeyColumn {
+            ident: quote_ident(name),
+            text: format!("cast(p.{} as text)", quote_ident(name)),
+            // Numeric affinity is applied to the text value when the column has it.
+            param: "?".to_string(),
+        })
+        .collect();
+    let query = keyset_query(&qualified, &columns, nav, cursor, page_size);
+
+    let mut q = sqlx::query(&query.sql);
+    for v in &query.binds {
+        q = q.bind(v);
+    }
+    let rows = q.fetch_all(pool).await?;
+
+    let (columns, rows, cursor) = keyset_page(
+        rows,
+        key.len(),
+        &query,
+        sqlite_column_meta,
+        sqlite_cell_value,
+    );
+    Ok(TablePage {
+        columns,
+        rows,
+        order: PageOrder::Key(key),
+        cursor,
+    })
+}
+
+pub async fn count_rows(pool: &SqlitePool, schema: &str, table: &str) -> Result<i64> {
+    let sql = format!(
+        "select count(*) from {}.{}",
+        quote_ident(schema),
+        quote_ident(table)
+    );
+    Ok(sqlx::query_scalar(&sql).fetch_one(pool).await?)
+}
+
+/// Runs on the session connection, which outlives the statement.
+pub fn execute_sql<'a>(
+    pool: &'a SqlitePool,
+    session: &'a SessionConn<Sqlite>,
+    sql: &'a str,
+    params: &'a [Option<String>],
+) -> BoxStream<'a, Result<SqlStep>> {
+    let stream = try_stream! {
+        let mut session = session.get(pool).await?;
+        let conn: &mut SqliteConnection = &mut session;
+        let columns: Vec<ColumnMeta> = describe::<Sqlite>(conn, sql)
+            .await?
+            .columns()
+            .iter()
+            .map(sqlite_column_meta)
+            .collect();
+        if !columns.is_empty() {
+            yield SqlStep::Columns(columns);
+        }
+
+        let mut query = sqlx::query(sql).persistent(false);
+        for value in params {
+            query = query.bind(value.as_deref());
+        }
+        let mut steps = conn.fetch_many(query);
+        while let Some(step) = steps.next().await {
+            yield sql_step(step?.map_left(|res| res.rows_affected()), sqlite_cell_value);
+        }
+    };
+    stream.boxed()
+}
@@ -0,0 +1,145 @@
+use anyhow::{bail, Context, Result};
+use std::process::{ExitStatus, Stdio};
+use tokio::io::AsyncReadExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::{Child, Command};
+use tokio::time::{sleep, Duration};
+
+use crate::model::connection::{KnownHosts, SshTunnel};
+
+/// A local port that `ssh -N -L` forwards to the database through a bastion.
+/// The forward lives as long as this value; dropping it kills ssh.
+pub struct Tunnel {
+    _child: Child,
+    local_port: u16,
+}
+
+impl Tunnel {
+    /// Starts ssh and waits until the forward accepts connections, or ssh
+    /// gives up. ssh runs in batch mode: it never prompts, so a key with a
+    /// passphrase has to be in the agent.
+    pub async fn open(
+        ssh: &SshTunnel,
+        db_host: &str,
+        db_port: u16,
+        connect_timeout: Duration,
+    ) -> Result<Self> {
+        let db_host = if db_host.contains(':') {
+            format!("[{db_host}]")
+        } else {
+            db_host.to_string()
+        };
+
+        let mut attempt = 1;
+        loop {
+            let local_port = TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port();
+            let (status, err) =
+                match start(ssh, local_port, &db_host, db_port, connect_timeout).await? {
+                    Started::Up(child) => {
+                        return Ok(Self {
+                            _child: child,
+                            local_port,
+                        })
+                    }
+                    Started::Exited(status, err) => (status, err),
+                };
+            // Something else can take the port between probing it and ssh
+            // binding it; another one will do.
+            if attempt < PORT_ATTEMPTS && port_taken(&err) {
+                attempt += 1;
+                continue;
+            }
+            bail!("SSH tunnel via {} failed ({status}): {err}", ssh.host);
+        }
+    }
+
+    pub fn local_port(&self) -> u16 {
+        self.local_port
+    }
+}
+
+/// Free local ports tried before giving up on the forward.
+const PORT_ATTEMPTS: usize = 3;
+
+/// How one ssh run went.
+enum Started {
+    /// The forward accepts connections.
+    Up(Child),
+    /// ssh exited, with what it wrote to stderr.
+    Exited(ExitStatus, String),
+}
+
+/// Runs ssh forwarding `local_port` and waits for the forward or its exit.
+async fn start(
+    ssh: &SshTunnel,
+    local_port: u16,
+    db_host: &str,
+    db_port: u16,
+    connect_timeout: Duration,
+) -> Result<Started> {
+    let mut cmd = Command::new("ssh");
+    cmd.arg("-N")
+        .args(["-o", "BatchMode=yes", "-o", "ExitOnForwardFailure=yes"])
+        .arg("-o")
+        .arg(format!(
+            "ConnectTimeout={}",
+            connect_timeout.as_secs().max(1)
+        ))
+        .arg("-L")
+        .arg(format!("127.0.0.1:{local_port}:{db_host}:{db_port}"))
+        .arg("-p")
+        .arg(ssh.port.to_string());
+    match ssh.known_hosts {
+        KnownHosts::Strict => cmd.args(["-o", "StrictHostKeyChecking=yes"]),
+        KnownHosts::AcceptNew => cmd.args(["-o", "StrictHostKeyChecking=accept-new"]),
+        KnownHosts::Off => cmd.args([
+            "-o",
+            "StrictHostKeyChecking=no",
+            "-o",
+            "UserKnownHostsFile=/dev/null",
+        ]),
+    };
+    if let Some(user) = &ssh.user {
+        cmd.arg("-l").arg(user);
+    }
+    if let Some(key) = &ssh.key {
+        cmd.arg("-i").arg(key).args(["-o", "IdentitiesOnly=yes"]);
+    }
+    // A host starting with `-` is still a host.
+    cmd.arg("--")
+        .arg(&ssh.host)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = cmd.spawn().context("could not start ssh")?;
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            let mut err = String::new();
+            if let Some(mut stderr) = child.stderr.take() {
+                let _ = stderr.read_to_string(&mut err).await;
+            }
+            return Ok(Started::Exited(status, err.trim().to_string()));
+        }
+        if TcpStream::connect(("127.0.0.1", local_port)).await.is_ok() {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+
+    // Keep reading what ssh reports, so it never blocks on a full pipe.
+    if let Some(mut stderr) = child.stderr.take() {
+        tokio::spawn(async move {
+            let _ = tokio::io::copy(&mut stderr, &mut tokio::io::sink()).await;
+        });
+    }
+
+    Ok(Started::Up(child))
+}
+
+/// Whether ssh gave up because the local end of the forward was in use.
+fn port_taken(stderr: &str) -> bool {
+    stderr.contains("Address already in use") || stderr.contains("cannot listen to port")
+}
@@ -1,114 +1,989 @@
-use anyhow::Result;
-use sqlx::postgres::PgPoolOptions;
-use sqlx::PgPool;
-use tokio::sync::mpsc;
-use tokio::time::{timeout, Duration};
+use anyhow::{bail, Context, Result};
+use futures::stream::{BoxStream, StreamExt};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex as SyncMutex};
+use tokio::sync::{mpsc, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::{timeout, Instant};
 
-use crate::db::{DbCmd, DbEvt};
+use crate::db::backend::{DatabaseBackend, SqlStep};
+use crate::db::{DbCmd, DbError, DbEvt, DbReply, DbRequest, Pane, RequestId};
+use crate::db::{StatementOutcome, TxStatus};
+use crate::model::connection::Driver;
+use crate::model::paging::{PageNav, PagePos, RowCount};
+use crate::model::plan::Plan;
+use crate::model::value::{CellValue, ColumnMeta, QueryParam};
+use crate::util::statement::{command_tag, is_query, split_statements};
+use crate::util::statement::{transaction_control, TxControl};
+use crate::util::{ddl, explain, params};
 
-pub async fn run(mut cmd_rx: mpsc::Receiver<DbCmd>, evt_tx: mpsc::Sender<DbEvt>) -> Result<()> {
-    let mut pool: Option<PgPool> = None;
+/// Rows per `DbEvt::RowBatch`.
+const BATCH_ROWS: usize = 500;
 
-    while let Some(cmd) = cmd_rx.recv().await {
+/// Sends the events answering one request, tagged with its id and pane.
+struct Replies {
+    tx: mpsc::Sender<DbReply>,
+    id: RequestId,
+    pane: Pane,
+}
+
+impl Replies {
+    async fn send(&self, evt: DbEvt) -> Result<(), mpsc::error::SendError<DbReply>> {
+        let reply = DbReply {
+            id: self.id,
+            pane: self.pane,
+            evt,
+        };
+        self.tx.send(reply).await
+    }
+}
+
+/// Worker-side state of the SQL session; it lives as long as the connection.
+struct State {
+    /// Commands that arrived while a query was streaming.
+    pending: VecDeque<DbRequest>,
+    tx: TxStatus,
+    autocommit: bool,
+}
+
+impl State {
+    async fn set_tx(&mut self, tx: TxStatus, evt_tx: &Replies) {
+        if self.tx != tx {
+            self.tx = tx;
+            let _ = evt_tx.send(DbEvt::TxStatus(tx)).await;
+        }
+    }
+}
+
+/// The task running the SQL tab's commands on the session connection.
+/// Dropping it stops whatever it was running.
+struct Session {
+    tx: mpsc::Sender<DbRequest>,
+    task: JoinHandle<()>,
+}
+
+impl Session {
+    fn start(
+        backend: Arc<dyn DatabaseBackend>,
+        reply_tx: mpsc::Sender<DbReply>,
+        autocommit: bool,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel(64);
+        let task = tokio::spawn(run_session(backend, rx, reply_tx, autocommit));
+        Self { tx, task }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Serves the UI's requests. Connecting happens here, in order. Commands on
+/// the session connection go to the session task, one at a time; every other
+/// command gets a task of its own on the pool, so Browse keeps paging while
+/// a long query runs in the SQL tab.
+pub async fn run(
+    mut cmd_rx: mpsc::Receiver<DbRequest>,
+    reply_tx: mpsc::Sender<DbReply>,
+) -> Result<()> {
+    let mut backend: Option<Arc<dyn DatabaseBackend>> = None;
+    let mut session: Option<Session> = None;
+    let mut autocommit = true;
+    let counts: Arc<Counts> = Arc::default();
+
+    while let Some(req) = cmd_rx.recv().await {
         #[cfg(debug_assertions)]
-        eprintln!("[worker] cmd: {:?}", cmd);
+        eprintln!("[worker] cmd #{}: {:?}", req.id.0, req.cmd);
+
+        let DbRequest { id, pane, cmd } = req;
+        let evt_tx = Replies {
+            tx: reply_tx.clone(),
+            id,
+            pane,
+        };
 
         match cmd {
-            DbCmd::Connect { database_url } => {
-                let _ = evt_tx.send(DbEvt::Status("Connecting…".into())).await;
+            DbCmd::Connect {
+                driver,
+                database_url,
+                options,
+            } => {
+                let status = match &options.ssh {
+                    Some(ssh) => format!("Connecting through {}…", ssh.host),
+                    None => "Connecting…".to_string(),
+                };
+                let _ = evt_tx.send(DbEvt::Status(status)).await;
 
-                let connect_fut = PgPoolOptions::new()
-                    .max_connections(6)
-                    .connect(&database_url);
+                // Any transaction lived on the old connection.
+                if session.take().is_some() {
+                    let _ = evt_tx.send(DbEvt::TxStatus(TxStatus::Idle)).await;
+                }
+
+                let connect_fut = crate::db::connect::connect(driver, &database_url, &options);
 
-                match timeout(Duration::from_secs(5), connect_fut).await {
-                    Ok(Ok(p)) => {
-                        pool = Some(p);
+                match timeout(options.connect_timeout(), connect_fut).await {
+                    Ok(Ok(b)) => {
+                        let b: Arc<dyn DatabaseBackend> = Arc::from(b);
+                        session = Some(Session::start(b.clone(), reply_tx.clone(), autocommit));
+                        backend = Some(b);
                         let _ = evt_tx.send(DbEvt::Status("Connected.".into())).await;
                     }
                     Ok(Err(e)) => {
-                        pool = None;
-                        let _ = evt_tx
-                            .send(DbEvt::Error(format!("DB connect failed: {e}")))
-                            .await;
+                        backend = None;
+                        let mut err = DbError::from_anyhow(&e);
+                        err.message = format!("DB connect failed: {}", err.message);
+                        let _ = evt_tx.send(DbEvt::Error(err)).await;
                     }
                     Err(_) => {
-                        pool = None;
+                        backend = None;
                         let _ = evt_tx
-                            .send(DbEvt::Error("DB connect timed out.".into()))
+                            .send(DbEvt::Error(DbError::msg("DB connect timed out.")))
                             .await;
                     }
                 }
             }
 
-            DbCmd::LoadTables { schema } => {
-                let Some(pool) = pool.as_ref() else {
-                    let _ = evt_tx.send(DbEvt::Error("Not connected.".into())).await;
-                    continue;
-                };
-                let tables = crate::db::postgres::load_tables(pool, &schema).await?;
-                let _ = evt_tx.send(DbEvt::TablesLoaded { tables }).await;
+            DbCmd::Cancel if pane == Pane::Browse => cancel_counts(&counts),
+
+            DbCmd::SetAutocommit(on) => {
+                autocommit = on;
+                if let Some(session) = &session {
+                    let _ = session.tx.send(DbRequest { id, pane, cmd }).await;
+                }
             }
 
-            DbCmd::LoadSqlMeta { schema } => {
-                let Some(pool) = pool.as_ref() else {
-                    let _ = evt_tx.send(DbEvt::Error("Not connected.".into())).await;
+            cmd if cmd.on_session() || matches!(cmd, DbCmd::Cancel) => match &session {
+                Some(session) => {
+                    let _ = session.tx.send(DbRequest { id, pane, cmd }).await;
+                }
+                None if matches!(cmd, DbCmd::Cancel) => {}
+                None => {
+                    let _ = evt_tx
+                        .send(DbEvt::Error(DbError::msg("Not connected.")))
+                        .await;
+                }
+            },
+
+            cmd => {
+                let Some(backend) = backend.clone() else {
+                    let _ = evt_tx
+                        .send(DbEvt::Error(DbError::msg("Not connected.")))
+                        .await;
                     continue;
                 };
+                let count_cancel = Arc::new(Notify::new());
+                if matches!(cmd, DbCmd::CountRows { .. }) {
+                    // Only the newest count is still wanted.
+                    cancel_counts(&counts);
+                    counts.lock().unwrap().insert(id, count_cancel.clone());
+                }
+                let counts = counts.clone();
 
-                // If you implemented LoadSqlMeta event, call those.
-                // Otherwise just load tables for now.
-                let tables = crate::db::postgres::load_tables(pool, &schema).await?;
-                let _ = evt_tx.send(DbEvt::TablesLoaded { tables }).await;
+                tokio::spawn(async move {
+                    // A failing statement is reported, never fatal: the worker keeps serving.
+                    if let Err(e) = handle(&*backend, cmd, &evt_tx, &count_cancel).await {
+                        let _ = evt_tx.send(DbEvt::Error(DbError::from_anyhow(&e))).await;
+                    }
+                    counts.lock().unwrap().remove(&id);
+                });
             }
+        }
+    }
 
-            DbCmd::LoadTablePage {
-                schema,
-                table,
-                page,
-                page_size,
-            } => {
-                let Some(pool) = pool.as_ref() else {
-                    let _ = evt_tx.send(DbEvt::Error("Not connected.".into())).await;
-                    continue;
-                };
+    Ok(())
+}
+
+/// The `CountRows` still running, each woken through its `Notify` to
+/// cancel itself.
+type Counts = SyncMutex<HashMap<RequestId, Arc<Notify>>>;
+
+/// Cancels every running count. Nothing happens when none is running.
+fn cancel_counts(counts: &Counts) {
+    for (_, cancel) in counts.lock().unwrap().drain() {
+        // Kept as a permit if the count has not started waiting yet.
+        cancel.notify_one();
+    }
+}
+
+/// Runs one command on the pool.
+async fn handle(
+    backend: &dyn DatabaseBackend,
+    cmd: DbCmd,
+    evt_tx: &Replies,
+    count_cancel: &Notify,
+) -> Result<()> {
+    match cmd {
+        DbCmd::LoadSchemas => {
+            let schemas = backend.list_schemas().await?;
+            let _ = evt_tx.send(DbEvt::SchemasLoaded { schemas }).await;
+        }
 
-                let (columns, rows) =
-                    crate::db::postgres::load_table_page(pool, &schema, &table, page, page_size)
-                        .await?;
+        DbCmd::LoadTables { schema } => {
+            let tables = backend.list_tables(&schema).await?;
+            let _ = evt_tx.send(DbEvt::TablesLoaded { tables }).await;
+        }
 
+        DbCmd::LoadSqlMeta { schema } => {
+            let catalog = backend.load_catalog(&schema).await?;
+            let _ = evt_tx.send(DbEvt::SqlMetaLoaded { catalog }).await;
+        }
+
+        DbCmd::LoadTablePage {
+            schema,
+            table,
+            nav,
+            cursor,
+            page_size,
+        } => {
+            let page = backend
+                .load_table_page(&schema, &table, nav, &cursor, page_size)
+                .await?;
+
+            // Paging past either end keeps the page on screen.
+            if page.rows.is_empty() && matches!(nav, PageNav::Next | PageNav::Prev) {
+                let _ = evt_tx.send(DbEvt::Status("No more rows.".into())).await;
+                return Ok(());
+            }
+
+            let info = match page.cursor.pos {
+                PagePos::FromStart(i) => format!("Loaded page {}", i + 1),
+                PagePos::FromEnd(0) => "Loaded last page".to_string(),
+                PagePos::FromEnd(i) => format!("Loaded page last-{i}"),
+            };
+            let _ = evt_tx
+                .send(DbEvt::QueryResult {
+                    columns: page.columns,
+                    rows: page.rows,
+                    order: page.order,
+                    cursor: page.cursor,
+                    info,
+                })
+                .await;
+
+            // Opening a table: a quick estimate until the user asks for a count.
+            if nav == PageNav::First {
+                if let Ok(Some(rows)) = backend.estimate_rows(&schema, &table).await {
+                    let count = RowCount { rows, exact: false };
+                    let _ = evt_tx
+                        .send(DbEvt::RowCount {
+                            schema,
+                            table,
+                            count,
+                        })
+                        .await;
+                }
+            }
+        }
+
+        DbCmd::RefreshMaterializedView { schema, table } => {
+            backend.refresh_materialized_view(&schema, &table).await?;
+            let _ = evt_tx
+                .send(DbEvt::MaterializedViewRefreshed { schema, table })
+                .await;
+        }
+
+        DbCmd::LoadDdl { schema, table } => {
+            let catalog = backend.load_catalog(&schema).await?;
+            let rel = catalog
+                .relation(&table)
+                .with_context(|| format!("{table} not found in {schema}"))?;
+            let sql = ddl::create_statements(backend.driver(), &schema, rel);
+            let _ = evt_tx.send(DbEvt::Ddl { table, sql }).await;
+        }
+
+        DbCmd::CountRows { schema, table } => {
+            let Some(rows) = count_rows(backend, &schema, &table, evt_tx, count_cancel).await?
+            else {
+                let _ = evt_tx.send(DbEvt::CountCancelled).await;
+                return Ok(());
+            };
+            let count = RowCount { rows, exact: true };
+            let _ = evt_tx
+                .send(DbEvt::RowCount {
+                    schema,
+                    table,
+                    count,
+                })
+                .await;
+        }
+
+        // Handled by `run` and `run_session`.
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Runs the SQL tab's commands in order on the session connection.
+/// While one runs, `Cancel` stops it and anything else waits its turn.
+async fn run_session(
+    backend: Arc<dyn DatabaseBackend>,
+    mut cmd_rx: mpsc::Receiver<DbRequest>,
+    reply_tx: mpsc::Sender<DbReply>,
+    autocommit: bool,
+) {
+    let backend = &*backend;
+    let mut state = State {
+        pending: VecDeque::new(),
+        tx: TxStatus::Idle,
+        autocommit,
+    };
+
+    loop {
+        let req = match state.pending.pop_front() {
+            Some(req) => req,
+            None => match cmd_rx.recv().await {
+                Some(req) => req,
+                None => break,
+            },
+        };
+
+        let DbRequest { id, pane, cmd } = req;
+        let evt_tx = Replies {
+            tx: reply_tx.clone(),
+            id,
+            pane,
+        };
+
+        match cmd {
+            // Nothing is running; a late Esc after the query finished.
+            DbCmd::Cancel => {}
+
+            DbCmd::SetAutocommit(on) => state.autocommit = on,
+
+            cmd => {
+                // A failing statement is reported, never fatal: the session keeps serving.
+                if let Err(e) = handle_session(backend, cmd, &evt_tx, &mut cmd_rx, &mut state).await
+                {
+                    let _ = evt_tx.send(DbEvt::Error(DbError::from_anyhow(&e))).await;
+                }
+            }
+        }
+    }
+}
+
+/// Runs one command on the session connection.
+async fn handle_session(
+    backend: &dyn DatabaseBackend,
+    cmd: DbCmd,
+    evt_tx: &Replies,
+    cmd_rx: &mut mpsc::Receiver<DbRequest>,
+    state: &mut State,
+) -> Result<()> {
+    match cmd {
+        DbCmd::ExecuteSql {
+            sql,
+            max_bytes,
+            stop_on_error,
+            params,
+        } => {
+            let script = Script {
+                sql: &sql,
+                params: &params,
+                max_bytes,
+                stop_on_error,
+            };
+            run_script(backend, script, evt_tx, cmd_rx, state).await;
+        }
+
+        DbCmd::DescribeParams { sql } => {
+            let params = describe_params(backend, &sql).await;
+            let _ = evt_tx.send(DbEvt::ParamsDescribed { sql, params }).await;
+        }
+
+        DbCmd::Explain {
+            sql,
+            analyze,
+            buffers,
+        } => {
+            if backend.driver() != Driver::Postgres {
+                bail!("EXPLAIN plans are only available on Postgres.");
+            }
+            let (result, savepoint) =
+                explain_plan(backend, &sql, analyze, buffers, evt_tx, cmd_rx, state).await;
+            // Like any statement, a failed ANALYZE aborts the open transaction
+            // unless it was rolled back to a savepoint.
+            let aborted = savepoint.leaves_aborted(matches!(result, Ok(Some(_))));
+            if aborted && state.tx == TxStatus::Active {
+                state.set_tx(TxStatus::Failed, evt_tx).await;
+            }
+            match result? {
+                Some(plan) => {
+                    let plan = Box::new(plan);
+                    let _ = evt_tx.send(DbEvt::PlanLoaded { plan }).await;
+                }
+                None => {
+                    let _ = evt_tx.send(DbEvt::ExplainCancelled).await;
+                }
+            }
+        }
+
+        DbCmd::Begin => {
+            let tag = transaction(backend, TxControl::Begin, "BEGIN", evt_tx, state).await?;
+            let _ = evt_tx.send(DbEvt::TxDone { tag }).await;
+        }
+        DbCmd::Commit => {
+            let tag = transaction(backend, TxControl::Commit, "COMMIT", evt_tx, state).await?;
+            let _ = evt_tx.send(DbEvt::TxDone { tag }).await;
+        }
+        DbCmd::Rollback => {
+            let tag = transaction(backend, TxControl::Rollback, "ROLLBACK", evt_tx, state).await?;
+            let _ = evt_tx.send(DbEvt::TxDone { tag }).await;
+        }
+
+        DbCmd::ResetSession => {
+            backend.reset_session().await?;
+            // Any transaction went with the old connection.
+            state.set_tx(TxStatus::Idle, evt_tx).await;
+            let _ = evt_tx.send(DbEvt::Status("Session reset.".into())).await;
+        }
+
+        // Handled by `run` and `run_session`.
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// An `ExecuteSql` script and how to run it.
+struct Script<'a> {
+    sql: &'a str,
+    params: &'a [QueryParam],
+    max_bytes: usize,
+    stop_on_error: bool,
+}
+
+/// Runs the statements of `script` one after another, reporting each with
+/// `StatementDone` and the whole run with `ScriptDone`. A cancel ends the
+/// script; a failure ends it only when `stop_on_error` is set.
+async fn run_script(
+    backend: &dyn DatabaseBackend,
+    script: Script<'_>,
+    evt_tx: &Replies,
+    cmd_rx: &mut mpsc::Receiver<DbRequest>,
+    state: &mut State,
+) {
+    let started = Instant::now();
+    let statements = split_statements(script.sql, backend.driver());
+    let count = statements.len();
+    let mut executed = 0;
+    let mut failed = 0;
+
+    for (index, stmt) in statements.into_iter().enumerate() {
+        let t = Instant::now();
+        let outcome = match run_statement(
+            backend,
+            &stmt.sql,
+            script.params,
+            script.max_bytes,
+            evt_tx,
+            cmd_rx,
+            state,
+        )
+        .await
+        {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                let mut err = DbError::from_anyhow(&e);
+                // Point into the script the user sees, not the single statement.
+                err.position = err.position.map(|p| p + stmt.offset);
+                StatementOutcome::Failed(err)
+            }
+        };
+        executed += 1;
+
+        // Postgres refuses everything after an error until the transaction is rolled back.
+        let aborted = matches!(
+            outcome,
+            StatementOutcome::Failed(_) | StatementOutcome::Cancelled { .. }
+        );
+        if aborted && state.tx == TxStatus::Active && backend.driver() == Driver::Postgres {
+            state.set_tx(TxStatus::Failed, evt_tx).await;
+        }
+
+        let stop = match outcome {
+            StatementOutcome::Failed(_) => {
+                failed += 1;
+                script.stop_on_error
+            }
+            StatementOutcome::Cancelled { .. } => true,
+            _ => false,
+        };
+
+        let _ = evt_tx
+            .send(DbEvt::StatementDone {
+                index,
+                count,
+                sql: stmt.sql,
+                outcome,
+                elapsed: t.elapsed(),
+            })
+            .await;
+
+        if stop {
+            break;
+        }
+    }
+
+    let _ = evt_tx
+        .send(DbEvt::ScriptDone {
+            executed,
+            count,
+            failed,
+            elapsed: started.elapsed(),
+        })
+        .await;
+}
+
+/// Transaction control updates the session's transaction state; anything else is
+/// streamed, after opening a transaction first when autocommit is off.
+async fn run_statement(
+    backend: &dyn DatabaseBackend,
+    sql: &str,
+    params: &[QueryParam],
+    max_bytes: usize,
+    evt_tx: &Replies,
+    cmd_rx: &mut mpsc::Receiver<DbRequest>,
+    state: &mut State,
+) -> Result<StatementOutcome> {
+    if let Some(op) = transaction_control(sql, backend.driver()) {
+        let tag = transaction(backend, op, sql, evt_tx, state).await?;
+        return Ok(StatementOutcome::Command { tag });
+    }
+
+    if !state.autocommit && state.tx == TxStatus::Idle {
+        transaction(backend, TxControl::Begin, "BEGIN", evt_tx, state).await?;
+    }
+
+    let (sql, values) = bind_params(backend, sql, params).await?;
+    stream_sql(
+        backend,
+        &sql,
+        &values,
+        max_bytes,
+        evt_tx,
+        cmd_rx,
+        &mut state.pending,
+    )
+    .await
+}
+
+/// Rewrites the placeholders of `sql` for the driver and lines up the values
+/// to bind; `sql` comes back unchanged when it has none.
+///
+/// On Postgres the statement is described again right before it runs: the
+/// types found when the form opened may predate tables the script creates.
+async fn bind_params(
+    backend: &dyn DatabaseBackend,
+    sql: &str,
+    params: &[QueryParam],
+) -> Result<(String, Vec<Option<String>>)> {
+    let driver = backend.driver();
+    let (plain, names) = params::positional_sql(sql, driver, &[]);
+    if names.is_empty() {
+        return Ok((sql.to_string(), Vec::new()));
+    }
+    let mut typed = params.to_vec();
+    if driver == Driver::Postgres {
+        let types = backend.describe_params(&plain).await?;
+        for (name, ty) in names.iter().zip(types) {
+            if let Some(p) = typed.iter_mut().find(|p| p.name == *name) {
+                p.type_name = ty;
+            }
+        }
+    }
+    let (sql, names) = params::positional_sql(sql, driver, &typed);
+    let values = names
+        .iter()
+        .map(|name| {
+            params
+                .iter()
+                .find(|p| p.name == *name)
+                .and_then(|p| p.value.clone())
+        })
+        .collect();
+    Ok((sql, values))
+}
+
+/// The placeholders of `script` in order of first use, typed from the first
+/// statement that uses each one. A statement that cannot be prepared yet,
+/// say on a table an earlier one creates, leaves its placeholders untyped.
+async fn describe_params(backend: &dyn DatabaseBackend, script: &str) -> Vec<QueryParam> {
+    let driver = backend.driver();
+    let mut out: Vec<QueryParam> = params::param_names(script, driver)
+        .into_iter()
+        .map(|name| QueryParam {
+            name,
+            ..QueryParam::default()
+        })
+        .collect();
+
+    for stmt in split_statements(script, driver) {
+        let (sql, names) = params::positional_sql(&stmt.sql, driver, &[]);
+        if names.is_empty() {
+            continue;
+        }
+        let types = backend.describe_params(&sql).await.unwrap_or_default();
+        for (name, ty) in names.iter().zip(types) {
+            if let Some(p) = out
+                .iter_mut()
+                .find(|p| p.name == *name && p.type_name.is_none())
+            {
+                p.type_name = ty;
+            }
+        }
+    }
+    out
+}
+
+/// Runs BEGIN / COMMIT / ROLLBACK on the session connection, reports the new
+/// transaction state and returns the command tag. `sql` is what the user
+/// typed for a BEGIN, so options like `ISOLATION LEVEL` are kept.
+async fn transaction(
+    backend: &dyn DatabaseBackend,
+    op: TxControl,
+    sql: &str,
+    evt_tx: &Replies,
+    state: &mut State,
+) -> Result<String> {
+    if op == TxControl::Begin {
+        if state.tx != TxStatus::Idle {
+            bail!("A transaction is already open.");
+        }
+        backend.begin(sql).await?;
+        state.set_tx(TxStatus::Active, evt_tx).await;
+        return Ok("BEGIN".to_string());
+    }
+
+    if state.tx == TxStatus::Idle {
+        bail!("No transaction is open.");
+    }
+    let was_failed = state.tx == TxStatus::Failed;
+    let result = match op {
+        TxControl::Commit => backend.commit().await,
+        _ => backend.rollback().await,
+    };
+    // Even a failed COMMIT ends the transaction.
+    state.set_tx(TxStatus::Idle, evt_tx).await;
+    result?;
+
+    Ok(match op {
+        // Postgres rolls back a failed transaction on COMMIT.
+        TxControl::Commit if !was_failed => "COMMIT",
+        _ => "ROLLBACK",
+    }
+    .to_string())
+}
+
+/// Waits for a `count(*)` until `cancel` wakes it; `None` if it was cancelled.
+async fn count_rows(
+    backend: &dyn DatabaseBackend,
+    schema: &str,
+    table: &str,
+    evt_tx: &Replies,
+    cancel: &Notify,
+) -> Result<Option<i64>> {
+    // Kept alive through the cancel, so the server still knows what to stop.
+    let count = backend.count_rows(evt_tx.id, schema, table);
+    tokio::pin!(count);
+
+    tokio::select! {
+        rows = &mut count => rows.map(Some),
+        _ = cancel.notified() => {
+            if let Err(e) = backend.cancel_count(evt_tx.id).await {
                 let _ = evt_tx
-                    .send(DbEvt::QueryResult {
-                        columns,
-                        rows,
-                        info: format!("Loaded page {}", page + 1),
-                    })
+                    .send(DbEvt::Status(format!("Cancel failed: {e:#}")))
                     .await;
             }
+            Ok(None)
+        }
+    }
+}
 
-            DbCmd::ExecuteSql { sql } => {
-                let Some(pool) = pool.as_ref() else {
-                    let _ = evt_tx.send(DbEvt::Error("Not connected.".into())).await;
-                    continue;
-                };
+/// Runs `EXPLAIN (FORMAT JSON)` on the session connection. ANALYZE runs the
+/// statement, so the changes of one that writes are rolled back afterwards,
+/// to a savepoint inside an open transaction; a query gets a transaction
+/// opened first when autocommit is off. The plan is `None` if it was
+/// cancelled; other commands arriving meanwhile are queued.
+async fn explain_plan(
+    backend: &dyn DatabaseBackend,
+    sql: &str,
+    analyze: bool,
+    buffers: bool,
+    evt_tx: &Replies,
+    cmd_rx: &mut mpsc::Receiver<DbRequest>,
+    state: &mut State,
+) -> (Result<Option<Plan>>, Savepoint) {
+    let undo = analyze && !is_query(sql, backend.driver());
+    let in_transaction = state.tx != TxStatus::Idle;
+    let opened = if undo && in_transaction {
+        execute(backend, &format!("savepoint {EXPLAIN_SAVEPOINT}")).await
+    } else if undo {
+        backend.begin("BEGIN").await
+    } else if analyze && !state.autocommit && !in_transaction {
+        transaction(backend, TxControl::Begin, "BEGIN", evt_tx, state)
+            .await
+            .map(drop)
+    } else {
+        Ok(())
+    };
+    if let Err(e) = opened {
+        return (Err(e), Savepoint::None);
+    }
 
-                match crate::db::postgres::execute_sql(pool, &sql).await? {
-                    crate::db::postgres::SqlExecResult::Rows { columns, rows } => {
-                        let _ = evt_tx
-                            .send(DbEvt::QueryResult {
-                                columns,
-                                rows,
-                                info: "Query OK".into(),
-                            })
-                            .await;
+    let stmt = explain::explain_statement(sql, analyze, buffers);
+    let plan = read_plan(backend, &stmt, evt_tx, cmd_rx, &mut state.pending).await;
+    if !undo {
+        return (plan, Savepoint::None);
+    }
+
+    if !in_transaction {
+        let undone = backend.rollback().await;
+        return (plan.and_then(|plan| undone.map(|()| plan)), Savepoint::None);
+    }
+    let (undone, savepoint) = match backend.rollback_to(EXPLAIN_SAVEPOINT).await {
+        Ok(()) => (
+            execute(backend, &format!("release savepoint {EXPLAIN_SAVEPOINT}")).await,
+            Savepoint::RolledBack,
+        ),
+        Err(e) => (Err(e), Savepoint::Failed),
+    };
+    (plan.and_then(|plan| undone.map(|()| plan)), savepoint)
+}
+
+/// How `explain_plan` left an open transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Savepoint {
+    /// None was taken; the transaction ran the EXPLAIN itself.
+    None,
+    /// Rolled back to after the EXPLAIN, whatever it did.
+    RolledBack,
+    /// Rolling back to it failed.
+    Failed,
+}
+
+impl Savepoint {
+    /// Whether the open transaction is aborted after an EXPLAIN that
+    /// `succeeded` or not.
+    fn leaves_aborted(self, succeeded: bool) -> bool {
+        match self {
+            Savepoint::None => !succeeded,
+            Savepoint::RolledBack => false,
+            Savepoint::Failed => true,
+        }
+    }
+}
+
+/// Where `explain_plan` rolls back to inside an open transaction.
+const EXPLAIN_SAVEPOINT: &str = "rustlens_explain";
+
+/// Runs an EXPLAIN and reads the plan from its single JSON cell; `None` if
+/// it was cancelled.
+async fn read_plan(
+    backend: &dyn DatabaseBackend,
+    stmt: &str,
+    evt_tx: &Replies,
+    cmd_rx: &mut mpsc::Receiver<DbRequest>,
+    pending: &mut VecDeque<DbRequest>,
+) -> Result<Option<Plan>> {
+    let mut stream = backend.execute_sql(stmt, &[]);
+    let mut output = None;
+
+    loop {
+        let step = tokio::select! {
+            step = stream.next() => step,
+            Some(req) = cmd_rx.recv() => {
+                match req.cmd {
+                    DbCmd::Cancel => {
+                        if let Err(e) = backend.cancel().await {
+                            let _ = evt_tx
+                                .send(DbEvt::Status(format!("Cancel failed: {e:#}")))
+                                .await;
+                        }
+                        return Ok(None);
                     }
-                    crate::db::postgres::SqlExecResult::Command { info } => {
-                        let _ = evt_tx.send(DbEvt::SqlExecuted { info }).await;
+                    _ => pending.push_back(req),
+                }
+                continue;
+            }
+        };
+        let Some(step) = step else { break };
+        if let SqlStep::Row(mut row) = step? {
+            if output.is_none() && !row.is_empty() {
+                output = Some(row.swap_remove(0));
+            }
+        }
+    }
+    drop(stream);
+    backend.finish_sql().await?;
+
+    let json = match output {
+        Some(CellValue::Json(v)) => v,
+        Some(CellValue::Text(t)) => {
+            serde_json::from_str(&t).context("EXPLAIN did not return JSON")?
+        }
+        _ => bail!("EXPLAIN returned no plan"),
+    };
+    explain::parse_plan(&json).map(Some)
+}
+
+/// Runs a statement whose result nobody reads.
+async fn execute(backend: &dyn DatabaseBackend, sql: &str) -> Result<()> {
+    let mut stream = backend.execute_sql(sql, &[]);
+    while let Some(step) = stream.next().await {
+        step?;
+    }
+    drop(stream);
+    backend.finish_sql().await
+}
+
+/// Forwards a query's rows to the UI in batches until the stream ends
+/// or the buffered rows reach `max_bytes`, then lets the backend release
+/// the rest of the result.
+///
+/// Keeps listening for commands meanwhile: `Cancel` stops the query,
+/// anything else is queued in `pending` for after it.
+async fn stream_sql(
+    backend: &dyn DatabaseBackend,
+    sql: &str,
+    params: &[Option<String>],
+    max_bytes: usize,
+    evt_tx: &Replies,
+    cmd_rx: &mut mpsc::Receiver<DbRequest>,
+    pending: &mut VecDeque<DbRequest>,
+) -> Result<StatementOutcome> {
+    let stream = backend.execute_sql(sql, params);
+    let outcome = forward_rows(backend, stream, sql, max_bytes, evt_tx, cmd_rx, pending).await;
+    let finished = backend.finish_sql().await;
+    let outcome = outcome?;
+    finished?;
+    Ok(outcome)
+}
+
+/// The loop of `stream_sql`; returning drops `stream`.
+async fn forward_rows(
+    backend: &dyn DatabaseBackend,
+    mut stream: BoxStream<'_, Result<SqlStep>>,
+    sql: &str,
+    max_bytes: usize,
+    evt_tx: &Replies,
+    cmd_rx: &mut mpsc::Receiver<DbRequest>,
+    pending: &mut VecDeque<DbRequest>,
+) -> Result<StatementOutcome> {
+    let mut columns: Option<Vec<ColumnMeta>> = None;
+    let mut returns_rows = false;
+    let mut batch: Vec<Vec<CellValue>> = Vec::new();
+    let mut total = 0usize;
+    let mut bytes = 0usize;
+    let mut more_available = false;
+    let mut rows_affected = 0u64;
+
+    loop {
+        let step = tokio::select! {
+            step = stream.next() => step,
+            Some(req) = cmd_rx.recv() => {
+                match req.cmd {
+                    DbCmd::Cancel => {
+                        // Ask the server first so it stops working, then drop our end.
+                        if let Err(e) = backend.cancel().await {
+                            let _ = evt_tx
+                                .send(DbEvt::Status(format!("Cancel failed: {e:#}")))
+                                .await;
+                        }
+                        drop(stream);
+                        if returns_rows {
+                            let _ = evt_tx
+                                .send(DbEvt::RowBatch {
+                                    columns: columns.take(),
+                                    rows: batch,
+                                })
+                                .await;
+                        }
+                        return Ok(StatementOutcome::Cancelled { rows: total });
                     }
+                    _ => pending.push_back(req),
                 }
+                continue;
+            }
+        };
+        let Some(step) = step else { break };
+
+        match step? {
+            SqlStep::Columns(c) => {
+                columns = Some(c);
+                returns_rows = true;
             }
+            SqlStep::Row(row) => {
+                if bytes >= max_bytes {
+                    more_available = true;
+                    break;
+                }
+                bytes += row.iter().map(CellValue::approx_size).sum::<usize>();
+                total += 1;
+                batch.push(row);
+
+                if batch.len() >= BATCH_ROWS {
+                    let rows = std::mem::take(&mut batch);
+                    let _ = evt_tx
+                        .send(DbEvt::RowBatch {
+                            columns: columns.take(),
+                            rows,
+                        })
+                        .await;
+                }
+            }
+            SqlStep::Done { rows_affected: n } => rows_affected += n,
         }
     }
 
-    Ok(())
+    if returns_rows {
+        let _ = evt_tx
+            .send(DbEvt::RowBatch {
+                columns: columns.take(),
+                rows: batch,
+            })
+            .await;
+        Ok(StatementOutcome::Rows {
+            total,
+            more_available,
+        })
+    } else {
+        Ok(StatementOutcome::Command {
+            tag: command_tag(sql, rows_affected, backend.driver()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explain_without_a_savepoint_aborts_on_failure() {
+        assert!(Savepoint::None.leaves_aborted(false));
+        assert!(!Savepoint::None.leaves_aborted(true));
+    }
+
+    #[test]
+    fn explain_rolled_back_to_a_savepoint_leaves_the_transaction() {
+        assert!(!Savepoint::RolledBack.leaves_aborted(false));
+        assert!(!Savepoint::RolledBack.leaves_aborted(true));
+        assert!(Savepoint::Failed.leaves_aborted(true));
+    }
+
+    #[tokio::test]
+    async fn cancelling_counts_wakes_each_one() {
+        let counts = Counts::default();
+        let first = Arc::new(Notify::new());
+        let second = Arc::new(Notify::new());
+        counts.lock().unwrap().insert(RequestId(1), first.clone());
+        counts.lock().unwrap().insert(RequestId(2), second.clone());
+
+        cancel_counts(&counts);
+        assert!(counts.lock().unwrap().is_empty());
+        first.notified().await;
+        second.notified().await;
+    }
 }
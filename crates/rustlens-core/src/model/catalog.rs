@@ -0,0 +1,171 @@
+/// What the database says about one schema's objects, loaded in one go
+/// for completion, browsing and editing.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    /// Every user schema on the connection, not just `schema`.
+    pub schemas: Vec<String>,
+    /// The schema `relations` belong to.
+    pub schema: String,
+    /// Sorted by name.
+    pub relations: Vec<Relation>,
+}
+
+impl Catalog {
+    pub fn relation(&self, name: &str) -> Option<&Relation> {
+        self.relations.iter().find(|r| r.name == name)
+    }
+}
+
+/// One entry of the Browse table list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableEntry {
+    pub name: String,
+    pub kind: RelationKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationKind {
+    Table,
+    View,
+    MaterializedView,
+    PartitionedTable,
+    ForeignTable,
+}
+
+impl RelationKind {
+    /// `pg_class.relkind` codes; anything else reads as a table.
+    pub fn from_relkind(code: &str) -> Self {
+        match code {
+            "v" => RelationKind::View,
+            "m" => RelationKind::MaterializedView,
+            "p" => RelationKind::PartitionedTable,
+            "f" => RelationKind::ForeignTable,
+            _ => RelationKind::Table,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Relation {
+    pub name: String,
+    pub kind: RelationKind,
+    /// In ordinal order.
+    pub columns: Vec<Column>,
+    pub primary_key: Option<PrimaryKey>,
+    pub uniques: Vec<UniqueConstraint>,
+    pub foreign_keys: Vec<ForeignKey>,
+    /// Includes the indexes behind primary key and unique constraints.
+    pub indexes: Vec<Index>,
+    pub checks: Vec<CheckConstraint>,
+    pub comment: Option<String>,
+    pub owner: Option<String>,
+    /// The `SELECT` behind a view or materialized view.
+    pub view_query: Option<String>,
+    /// `PARTITION BY` clause of a partitioned table, without the keywords.
+    pub partition_key: Option<String>,
+    /// Server a foreign table reads from.
+    pub foreign_server: Option<String>,
+}
+
+impl Relation {
+    pub fn new(name: String, kind: RelationKind) -> Self {
+        Self {
+            name,
+            kind,
+            columns: Vec::new(),
+            primary_key: None,
+            uniques: Vec::new(),
+            foreign_keys: Vec::new(),
+            indexes: Vec::new(),
+            checks: Vec::new(),
+            comment: None,
+            owner: None,
+            view_query: None,
+            partition_key: None,
+            foreign_server: None,
+        }
+    }
+
+    pub fn entry(&self) -> TableEntry {
+        TableEntry {
+            name: self.name.clone(),
+            kind: self.kind,
+        }
+    }
+
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|c| c.name == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub name: String,
+    /// As the server spells it, modifiers included (`character varying(20)`).
+    pub data_type: String,
+    pub nullable: bool,
+    /// Default expression, as SQL text.
+    pub default: Option<String>,
+    pub identity: Option<Identity>,
+    /// Expression of a generated column; empty on SQLite, which does not
+    /// expose it.
+    pub generated: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// How a column numbers itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Identity {
+    /// `GENERATED ALWAYS AS IDENTITY`: explicit values are refused.
+    Always,
+    /// `GENERATED BY DEFAULT AS IDENTITY`, MySQL `AUTO_INCREMENT`,
+    /// a SQLite `INTEGER PRIMARY KEY`.
+    ByDefault,
+}
+
+#[derive(Debug, Clone)]
+pub struct PrimaryKey {
+    /// Constraint name; SQLite does not keep one.
+    pub name: Option<String>,
+    pub columns: Vec<String>,
+}
+
+/// A `UNIQUE` constraint, as opposed to a unique index created on its own.
+#[derive(Debug, Clone)]
+pub struct UniqueConstraint {
+    pub name: Option<String>,
+    pub columns: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ForeignKey {
+    pub name: Option<String>,
+    pub columns: Vec<String>,
+    pub ref_schema: String,
+    pub ref_table: String,
+    /// Empty when SQLite leaves them to the referenced table's primary key.
+    pub ref_columns: Vec<String>,
+    /// Referential actions (`CASCADE`, `SET NULL`, `NO ACTION`, …).
+    pub on_update: String,
+    pub on_delete: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Index {
+    pub name: String,
+    /// Key columns in order; expression parts as SQL text.
+    pub columns: Vec<String>,
+    pub unique: bool,
+    pub primary: bool,
+    /// `WHERE` clause of a partial index.
+    pub predicate: Option<String>,
+    /// The `CREATE INDEX` statement, where the server keeps one.
+    pub definition: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckConstraint {
+    pub name: Option<String>,
+    /// The condition, without `CHECK`.
+    pub expression: String,
+}
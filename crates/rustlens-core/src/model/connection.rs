@@ -1,8 +1,14 @@
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 use uuid::Uuid;
 
+/// A saved connection, as a `[[profiles]]` entry of config.toml.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectionProfile {
+    #[serde(default = "Uuid::new_v4")]
     pub id: Uuid,
     pub name: String,
     pub driver: Driver,
@@ -13,10 +19,199 @@ pub struct ConnectionProfile {
     pub schema: Option<String>,
     #[serde(default)]
     pub page_size: Option<i64>,
+
+    #[serde(flatten)]
+    pub options: ConnectionOptions,
+}
+
+impl ConnectionProfile {
+    pub fn new(name: impl Into<String>, driver: Driver, database_url: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            driver,
+            database_url: database_url.into(),
+            schema: None,
+            page_size: None,
+            options: ConnectionOptions::default(),
+        }
+    }
+}
+
+/// How connections are opened, on top of what the URL says. Set TLS fields
+/// override the URL's `sslmode`, `sslrootcert`, … parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionOptions {
+    /// Directory of the server's Unix socket, e.g. `/var/run/postgresql`
+    /// (for MySQL, the socket file itself). Replaces the URL's host, so
+    /// peer-authenticated local databases need no password.
+    pub host: Option<PathBuf>,
+    /// Unset leaves it to the URL, which defaults to `prefer`. Ignored on a
+    /// Unix socket, as libpq does.
+    pub sslmode: Option<SslMode>,
+    /// CA certificate the server's certificate must chain to.
+    pub sslrootcert: Option<PathBuf>,
+    /// Client certificate and key, for servers that ask for one.
+    pub sslcert: Option<PathBuf>,
+    pub sslkey: Option<PathBuf>,
+    /// Seconds to wait for a connection before giving up.
+    pub connect_timeout: u64,
+    /// Seconds an unused pooled connection stays open; 0 keeps it open.
+    pub idle_timeout: u64,
+    /// Pooled connections Browse may use at once. The SQL tab's own
+    /// connection comes on top. SQLite always uses one.
+    pub max_connections: u32,
+    /// Shows up in `pg_stat_activity` (Postgres only).
+    pub application_name: String,
+    /// Reach the database through an SSH bastion.
+    pub ssh: Option<SshTunnel>,
+}
+
+/// An SSH hop in front of the database: the URL's host and port are
+/// forwarded from a local port on this machine, through `host`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SshTunnel {
+    pub host: String,
+    #[serde(default = "default_ssh_port")]
+    pub port: u16,
+    /// Defaults to what `~/.ssh/config` says, or the local user name.
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Private key; without one, the agent and `~/.ssh/config` decide.
+    #[serde(default)]
+    pub key: Option<PathBuf>,
+    #[serde(default)]
+    pub known_hosts: KnownHosts,
+}
+
+fn default_ssh_port() -> u16 {
+    22
+}
+
+/// What to do with the bastion's host key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KnownHosts {
+    /// Only connect to hosts already in `known_hosts`.
+    Strict,
+    /// Trust and remember a host seen for the first time; refuse a changed key.
+    #[default]
+    AcceptNew,
+    /// Do not check the host key at all.
+    Off,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        Self {
+            host: None,
+            sslmode: None,
+            sslrootcert: None,
+            sslcert: None,
+            sslkey: None,
+            connect_timeout: 5,
+            idle_timeout: 600,
+            max_connections: 6,
+            application_name: "rustlens".into(),
+            ssh: None,
+        }
+    }
+}
+
+impl ConnectionOptions {
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.connect_timeout)
+    }
+
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        (self.idle_timeout > 0).then(|| Duration::from_secs(self.idle_timeout))
+    }
+}
+
+/// TLS modes, as libpq names them. MySQL has no `allow`; it is taken as `prefer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    Disable,
+    Allow,
+    Prefer,
+    Require,
+    /// Require TLS and check the certificate against `sslrootcert`.
+    VerifyCa,
+    /// Like `VerifyCa`, and the certificate must also name the host.
+    VerifyFull,
+}
+
+impl FromStr for SslMode {
+    type Err = anyhow::Error;
+
+    /// Reads the libpq names, as `sslmode=` takes them.
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "disable" => SslMode::Disable,
+            "allow" => SslMode::Allow,
+            "prefer" => SslMode::Prefer,
+            "require" => SslMode::Require,
+            "verify-ca" => SslMode::VerifyCa,
+            "verify-full" => SslMode::VerifyFull,
+            _ => bail!("Unknown sslmode: {s}"),
+        })
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum Driver {
     Postgres,
+    Sqlite,
+    Mysql,
+}
+
+impl Driver {
+    /// Guesses the driver from a connection string's scheme.
+    /// Bare paths ending in `.db`, `.sqlite` or `.sqlite3` are taken as SQLite files.
+    pub fn from_url(database_url: &str) -> Option<Self> {
+        let lower = database_url.to_ascii_lowercase();
+        if lower.starts_with("sqlite:") {
+            return Some(Driver::Sqlite);
+        }
+
+        match lower.split_once("://") {
+            Some(("postgres" | "postgresql", _)) => Some(Driver::Postgres),
+            Some(("mysql" | "mariadb", _)) => Some(Driver::Mysql),
+            Some(_) => None,
+            None if is_sqlite_path(&lower) => Some(Driver::Sqlite),
+            None => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Driver::Postgres => "postgres",
+            Driver::Sqlite => "sqlite",
+            Driver::Mysql => "mysql",
+        }
+    }
+
+    /// Schema to browse when none was given.
+    /// MySQL has no default schema, so the database named in the URL is used.
+    pub fn default_schema(self, database_url: &str) -> String {
+        match self {
+            Driver::Postgres => "public".into(),
+            Driver::Sqlite => "main".into(),
+            Driver::Mysql => database_url
+                .split_once("://")
+                .and_then(|(_, rest)| rest.split_once('/'))
+                .map(|(_, path)| path.split('?').next().unwrap_or_default())
+                .unwrap_or_default()
+                .to_string(),
+        }
+    }
+}
+
+fn is_sqlite_path(path: &str) -> bool {
+    [".db", ".sqlite", ".sqlite3"]
+        .iter()
+        .any(|ext| path.ends_with(ext))
 }
@@ -1 +1,5 @@
+pub mod catalog;
 pub mod connection;
+pub mod plan;
+pub mod paging;
+pub mod value;
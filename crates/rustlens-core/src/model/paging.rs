@@ -0,0 +1,85 @@
+use crate::model::value::{CellValue, ColumnMeta};
+
+/// Which page of a table to load, relative to the one on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageNav {
+    First,
+    Next,
+    Prev,
+    Last,
+}
+
+/// Where a page sits in the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PagePos {
+    /// 0-based page number.
+    FromStart(i64),
+    /// Pages before the last one; keyset paging reaches the end without
+    /// counting rows, so after a jump to the last page only this is known.
+    FromEnd(i64),
+}
+
+impl Default for PagePos {
+    fn default() -> Self {
+        PagePos::FromStart(0)
+    }
+}
+
+/// The page on screen, handed back with the next `PageNav`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageCursor {
+    pub pos: PagePos,
+    /// Key values of the first and last row, as text. Empty for OFFSET paging
+    /// and for an empty page.
+    pub first_key: Option<Vec<String>>,
+    pub last_key: Option<Vec<String>>,
+    /// Known to be the last page: paging forward came up short, or this is
+    /// where a jump to the last page landed.
+    pub at_end: bool,
+}
+
+/// How a table's rows are ordered for paging.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageOrder {
+    /// Primary key or unique index columns, paged with `WHERE (key) > (…)`.
+    Key(Vec<String>),
+    /// No usable key: `LIMIT … OFFSET …` in physical order where there is one
+    /// (`ctid` on Postgres), otherwise in scan order.
+    Offset,
+}
+
+/// Rows in a table, from the planner's statistics or a `count(*)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowCount {
+    pub rows: i64,
+    pub exact: bool,
+}
+
+impl RowCount {
+    pub fn pages(&self, page_size: i64) -> i64 {
+        let size = page_size.max(1);
+        ((self.rows + size - 1) / size).max(1)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TablePage {
+    pub columns: Vec<ColumnMeta>,
+    pub rows: Vec<Vec<CellValue>>,
+    pub order: PageOrder,
+    pub cursor: PageCursor,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_partial_and_empty_pages() {
+        let count = |rows| RowCount { rows, exact: true };
+        assert_eq!(count(0).pages(200), 1);
+        assert_eq!(count(200).pages(200), 1);
+        assert_eq!(count(201).pages(200), 2);
+        assert_eq!(count(5).pages(0), 5);
+    }
+}
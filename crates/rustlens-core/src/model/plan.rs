@@ -0,0 +1,70 @@
+/// A query plan as `EXPLAIN (FORMAT JSON)` reports it.
+#[derive(Debug, Clone, Default)]
+pub struct Plan {
+    pub root: PlanNode,
+    /// Ran with ANALYZE: nodes carry actual rows and times.
+    pub analyzed: bool,
+    pub planning_ms: Option<f64>,
+    pub execution_ms: Option<f64>,
+}
+
+/// One node of the plan tree. Costs are in the planner's units; rows and
+/// times are per loop, as Postgres reports them.
+#[derive(Debug, Clone, Default)]
+pub struct PlanNode {
+    /// `Hash Left Join`, `Index Scan`, …: the name text EXPLAIN would show.
+    pub node_type: String,
+    /// What the node works on, e.g. `using users_pkey on users u`.
+    pub detail: Option<String>,
+    pub startup_cost: f64,
+    pub total_cost: f64,
+    pub plan_rows: f64,
+    pub actual_rows: Option<f64>,
+    pub actual_loops: Option<f64>,
+    pub actual_total_ms: Option<f64>,
+    /// Only with BUFFERS; counts include the node's children.
+    pub buffers: Option<Buffers>,
+    pub children: Vec<PlanNode>,
+}
+
+/// Blocks a node touched, from `EXPLAIN (BUFFERS)`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Buffers {
+    pub shared_hit: i64,
+    pub shared_read: i64,
+    pub temp_read: i64,
+    pub temp_written: i64,
+}
+
+impl PlanNode {
+    /// Time spent in this node over all loops, children included.
+    pub fn total_ms(&self) -> Option<f64> {
+        Some(self.actual_total_ms? * self.actual_loops.unwrap_or(1.0))
+    }
+
+    /// Time spent in this node alone.
+    pub fn self_ms(&self) -> Option<f64> {
+        let children: f64 = self.children.iter().filter_map(PlanNode::total_ms).sum();
+        Some((self.total_ms()? - children).max(0.0))
+    }
+
+    /// Cost of this node alone, without what its children add.
+    pub fn self_cost(&self) -> f64 {
+        let children: f64 = self.children.iter().map(|c| c.total_cost).sum();
+        (self.total_cost - children).max(0.0)
+    }
+
+    /// Share of the whole plan this node accounts for by itself, 0.0 to 1.0:
+    /// of the time when the plan was analyzed, of the cost otherwise.
+    pub fn share_of(&self, plan: &Plan) -> f64 {
+        let (own, whole) = match (self.self_ms(), plan.root.total_ms()) {
+            (Some(own), Some(whole)) => (own, whole),
+            _ => (self.self_cost(), plan.root.total_cost),
+        };
+        if whole > 0.0 {
+            (own / whole).min(1.0)
+        } else {
+            0.0
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A single decoded cell. Rendering to text happens in the UI,
+/// see `util::value_fmt::cell_to_string`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    /// Arbitrary precision decimals, kept as the server's text form.
+    Numeric(String),
+    Text(String),
+    Json(serde_json::Value),
+    Bytes(Vec<u8>),
+    Uuid(Uuid),
+    Timestamp(NaiveDateTime),
+    TimestampTz(DateTime<Utc>),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    /// The driver could not decode the value; holds the SQL type name.
+    Unsupported(String),
+}
+
+impl CellValue {
+    pub fn is_null(&self) -> bool {
+        matches!(self, CellValue::Null)
+    }
+
+    /// Rough in-memory footprint, used to cap how much of a result is kept.
+    pub fn approx_size(&self) -> usize {
+        let heap = match self {
+            CellValue::Numeric(v) | CellValue::Text(v) | CellValue::Unsupported(v) => v.len(),
+            CellValue::Bytes(v) => v.len(),
+            // Serialized length is a fair stand-in for the tree's allocations.
+            CellValue::Json(v) => v.to_string().len(),
+            _ => 0,
+        };
+        std::mem::size_of::<Self>() + heap
+    }
+}
+
+/// Coarse classification of a column's SQL type, shared by all drivers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Bool,
+    Integer,
+    Float,
+    Numeric,
+    Text,
+    Json,
+    Binary,
+    Uuid,
+    Temporal,
+    Other,
+}
+
+impl ColumnKind {
+    pub fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            ColumnKind::Integer | ColumnKind::Float | ColumnKind::Numeric
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMeta {
+    pub name: String,
+    /// Type name as reported by the driver (`INT4`, `VARCHAR`, `BLOB`, …).
+    pub type_name: String,
+    pub kind: ColumnKind,
+}
+
+/// A placeholder of a parameterized query and the text bound to it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryParam {
+    /// `1` for `$1`, `name` for `:name`.
+    pub name: String,
+    /// The type the server expects, where the driver reports one (Postgres).
+    pub type_name: Option<String>,
+    /// `None` binds NULL.
+    pub value: Option<String>,
+}
@@ -0,0 +1,252 @@
+use anyhow::{bail, Context, Result};
+use std::env::var_os;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A libpq `keyword=value` string such as `service=reporting` or
+/// `host=db dbname=app`, rather than a URL or a file that happens to have
+/// a `=` in its name.
+pub fn is_conninfo(s: &str) -> bool {
+    if s.contains("://") || Path::new(s).exists() {
+        return false;
+    }
+    match s.trim_start().split_once('=') {
+        Some((key, _)) => {
+            let key = key.trim_end();
+            !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+/// Turns a conninfo string into a `postgres://` URL the libpq way:
+/// `service=` (or `PGSERVICE`) pulls in its `pg_service.conf` section,
+/// keywords given here win over the service's, and whatever is still
+/// missing comes from the `PG*` environment, the password from `.pgpass`.
+/// An empty string names no parameters at all. Keywords the driver does not
+/// know, such as `connect_timeout`, are passed on and ignored there.
+pub fn conninfo_url(conninfo: &str) -> Result<String> {
+    let given = parse(conninfo)?;
+    let service = given
+        .iter()
+        .find(|(k, _)| k == "service")
+        .map(|(_, v)| v.clone())
+        .or_else(|| var_os("PGSERVICE").map(|s| s.to_string_lossy().into_owned()));
+
+    let mut params = match service {
+        Some(name) => service_params(&name)?,
+        None => Vec::new(),
+    };
+    for (key, value) in given.into_iter().filter(|(k, _)| k != "service") {
+        params.retain(|(k, _)| *k != key);
+        params.push((key, value));
+    }
+
+    // Left out of the URL, they fall back to the environment and `.pgpass`
+    // when the connection is opened. An empty value counts as left out.
+    let query: Vec<String> = params
+        .iter()
+        .filter(|(_, v)| !v.is_empty())
+        .map(|(k, v)| format!("{k}={}", percent_encode(v)))
+        .collect();
+    if query.is_empty() {
+        return Ok("postgres://".to_string());
+    }
+    Ok(format!("postgres://?{}", query.join("&")))
+}
+
+/// Splits `key=value key='quoted value'` pairs; `\'` and `\\` escape inside quotes.
+fn parse(conninfo: &str) -> Result<Vec<(String, String)>> {
+    let chars: Vec<char> = conninfo.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+
+    loop {
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        if i == chars.len() {
+            return Ok(out);
+        }
+
+        let start = i;
+        while i < chars.len() && chars[i] != '=' && !chars[i].is_whitespace() {
+            i += 1;
+        }
+        let key: String = chars[start..i].iter().collect();
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        if chars.get(i) != Some(&'=') {
+            bail!("missing \"=\" after \"{key}\" in connection string");
+        }
+        i += 1;
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+
+        let mut value = String::new();
+        if chars.get(i) == Some(&'\'') {
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => bail!("unterminated quoted value for \"{key}\""),
+                    Some('\'') => break,
+                    Some('\\') if i + 1 < chars.len() => {
+                        value.push(chars[i + 1]);
+                        i += 2;
+                        continue;
+                    }
+                    Some(&c) => value.push(c),
+                }
+                i += 1;
+            }
+            i += 1;
+        } else {
+            while i < chars.len() && !chars[i].is_whitespace() {
+                value.push(chars[i]);
+                i += 1;
+            }
+        }
+
+        out.push((key, value));
+    }
+}
+
+/// The settings of service `name`, from the user's service file if it has
+/// the service, else from the system-wide one.
+fn service_params(name: &str) -> Result<Vec<(String, String)>> {
+    for path in service_files() {
+        if let Some(params) = read_service(&path, name)? {
+            return Ok(params);
+        }
+    }
+    bail!("definition of service \"{name}\" not found")
+}
+
+/// `PGSERVICEFILE` or `~/.pg_service.conf`, then `pg_service.conf` in
+/// `PGSYSCONFDIR` or the Debian default.
+fn service_files() -> Vec<PathBuf> {
+    let user = var_os("PGSERVICEFILE")
+        .map(PathBuf::from)
+        .or_else(|| var_os("HOME").map(|home| Path::new(&home).join(".pg_service.conf")));
+    let system = var_os("PGSYSCONFDIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/etc/postgresql-common"))
+        .join("pg_service.conf");
+    user.into_iter().chain([system]).collect()
+}
+
+/// The `[name]` section of an INI-style service file; `None` when the file
+/// or the section does not exist.
+fn read_service(path: &Path, name: &str) -> Result<Option<Vec<(String, String)>>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("could not read {}", path.display())),
+    };
+
+    let mut section: Option<Vec<(String, String)>> = None;
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            if section.is_some() {
+                break;
+            }
+            if header.trim() == name {
+                section = Some(Vec::new());
+            }
+            continue;
+        }
+        let Some(params) = &mut section else {
+            continue;
+        };
+        let Some((key, value)) = line.split_once('=') else {
+            bail!("syntax error in {}, line {}", path.display(), n + 1);
+        };
+        params.push((key.trim().to_string(), value.trim().to_string()));
+    }
+    Ok(section)
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quoted_and_escaped_values() {
+        let params = parse(r"host=db  port = 5433 dbname='my \'app\'' password='a\\b'").unwrap();
+        let expected = [
+            ("host", "db"),
+            ("port", "5433"),
+            ("dbname", "my 'app'"),
+            ("password", r"a\b"),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        assert_eq!(params, expected);
+    }
+
+    #[test]
+    fn rejects_malformed_strings() {
+        assert!(parse("host").is_err());
+        assert!(parse("dbname='app").is_err());
+    }
+
+    #[test]
+    fn reads_only_the_named_service() {
+        let path = std::env::temp_dir().join(format!("rustlens-svc-{}.conf", std::process::id()));
+        let text =
+            "# services\n[other]\nhost=elsewhere\n\n[reporting]\nhost = db\nconnect_timeout=10\n";
+        fs::write(&path, text).unwrap();
+        let service = read_service(&path, "reporting");
+        let missing = read_service(&path, "absent");
+        fs::remove_file(&path).unwrap();
+
+        let expected = vec![
+            ("host".to_string(), "db".to_string()),
+            ("connect_timeout".to_string(), "10".to_string()),
+        ];
+        assert_eq!(service.unwrap(), Some(expected));
+        assert_eq!(missing.unwrap(), None);
+        assert_eq!(read_service(&path, "reporting").unwrap(), None);
+    }
+
+    #[test]
+    fn builds_a_url_from_keywords() {
+        // Without `service=`, only a `PGSERVICE` in the environment adds more.
+        if var_os("PGSERVICE").is_some() {
+            return;
+        }
+        assert_eq!(conninfo_url("").unwrap(), "postgres://");
+        assert_eq!(
+            conninfo_url("host=/var/run/postgresql dbname='my app' user=").unwrap(),
+            "postgres://?host=%2Fvar%2Frun%2Fpostgresql&dbname=my%20app"
+        );
+    }
+
+    #[test]
+    fn tells_conninfo_from_urls_and_paths() {
+        assert!(is_conninfo("service=reporting"));
+        assert!(is_conninfo(" host = db dbname=app"));
+        assert!(!is_conninfo("postgres://db/app?sslmode=require"));
+        assert!(!is_conninfo("./data/a=b.db"));
+        assert!(!is_conninfo("data/app.db"));
+    }
+}
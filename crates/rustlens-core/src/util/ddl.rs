@@ -0,0 +1,313 @@
+use crate::model::catalog::{Column, Identity, Relation, RelationKind};
+use crate::model::connection::Driver;
+
+/// Rebuilds the statements that create `rel` in `schema`: the CREATE itself
+/// with columns and constraints, then indexes, comments and ownership, each
+/// ending in `;`. SQLite keeps no CHECK constraints or generated column
+/// expressions in the catalog, so those come out missing or marked.
+pub fn create_statements(driver: Driver, schema: &str, rel: &Relation) -> String {
+    let q = |s: &str| quote_ident(driver, s);
+    let name = format!("{}.{}", q(schema), q(&rel.name));
+
+    let mut out = match rel.kind {
+        RelationKind::View | RelationKind::MaterializedView => {
+            let query = rel.view_query.as_deref().unwrap_or_default().trim();
+            format!(
+                "CREATE {} {name} AS\n{};\n",
+                kind_keyword(rel.kind),
+                query.trim_end_matches(';')
+            )
+        }
+        _ => create_table(driver, &name, rel),
+    };
+
+    let indexes: Vec<String> = rel
+        .indexes
+        .iter()
+        .filter(|ix| !ix.primary)
+        // Those behind a UNIQUE constraint come with it.
+        .filter(|ix| {
+            !rel.uniques
+                .iter()
+                .any(|u| u.name.as_ref() == Some(&ix.name))
+        })
+        .filter_map(|ix| ix.definition.as_deref())
+        .map(|def| format!("{def};\n"))
+        .collect();
+    // MySQL has no statement for its indexes; they are part of CREATE TABLE.
+    if !indexes.is_empty() && driver != Driver::Mysql {
+        out.push('\n');
+        out.extend(indexes);
+    }
+
+    if driver == Driver::Postgres {
+        let kind = kind_keyword(rel.kind);
+        let mut comments = Vec::new();
+        if let Some(c) = &rel.comment {
+            comments.push(format!(
+                "COMMENT ON {kind} {name} IS {};\n",
+                quote_literal(c)
+            ));
+        }
+        for col in &rel.columns {
+            if let Some(c) = &col.comment {
+                comments.push(format!(
+                    "COMMENT ON COLUMN {name}.{} IS {};\n",
+                    q(&col.name),
+                    quote_literal(c)
+                ));
+            }
+        }
+        if !comments.is_empty() {
+            out.push('\n');
+            out.extend(comments);
+        }
+        if let Some(owner) = &rel.owner {
+            out += &format!("\nALTER {kind} {name} OWNER TO {};\n", q(owner));
+        }
+    }
+
+    out
+}
+
+fn create_table(driver: Driver, name: &str, rel: &Relation) -> String {
+    let q = |s: &str| quote_ident(driver, s);
+    let cols = |cols: &[String]| cols.iter().map(|c| q(c)).collect::<Vec<_>>().join(", ");
+    let constraint = |n: &Option<String>| match n {
+        Some(n) => format!("CONSTRAINT {} ", q(n)),
+        None => String::new(),
+    };
+
+    let mut lines: Vec<String> = rel.columns.iter().map(|c| column(driver, c)).collect();
+
+    if let Some(pk) = &rel.primary_key {
+        let named = if driver == Driver::Mysql {
+            String::new()
+        } else {
+            constraint(&pk.name)
+        };
+        lines.push(format!("{named}PRIMARY KEY ({})", cols(&pk.columns)));
+    }
+    for u in &rel.uniques {
+        lines.push(format!(
+            "{}UNIQUE ({})",
+            constraint(&u.name),
+            cols(&u.columns)
+        ));
+    }
+    if driver == Driver::Mysql {
+        for ix in rel.indexes.iter().filter(|ix| !ix.primary) {
+            let unique = if ix.unique { "UNIQUE " } else { "" };
+            lines.push(format!(
+                "{unique}KEY {} ({})",
+                q(&ix.name),
+                cols(&ix.columns)
+            ));
+        }
+    }
+    for fk in &rel.foreign_keys {
+        // SQLite only references tables in the same database.
+        let target = if driver == Driver::Sqlite {
+            q(&fk.ref_table)
+        } else {
+            format!("{}.{}", q(&fk.ref_schema), q(&fk.ref_table))
+        };
+        let mut line = format!(
+            "{}FOREIGN KEY ({}) REFERENCES {target}",
+            constraint(&fk.name),
+            cols(&fk.columns)
+        );
+        if !fk.ref_columns.is_empty() {
+            line += &format!(" ({})", cols(&fk.ref_columns));
+        }
+        for (on, action) in [("UPDATE", &fk.on_update), ("DELETE", &fk.on_delete)] {
+            if action != "NO ACTION" {
+                line += &format!(" ON {on} {action}");
+            }
+        }
+        lines.push(line);
+    }
+    for ck in &rel.checks {
+        lines.push(format!("{}CHECK ({})", constraint(&ck.name), ck.expression));
+    }
+
+    let kind = match rel.kind {
+        RelationKind::ForeignTable => "FOREIGN TABLE",
+        _ => "TABLE",
+    };
+    let mut out = format!("CREATE {kind} {name} (\n    {}\n)", lines.join(",\n    "));
+    if let Some(key) = &rel.partition_key {
+        out += &format!(" PARTITION BY {key}");
+    }
+    if let Some(server) = &rel.foreign_server {
+        out += &format!(" SERVER {}", q(server));
+    }
+    if let (Driver::Mysql, Some(c)) = (driver, &rel.comment) {
+        out += &format!(" COMMENT={}", quote_literal(c));
+    }
+    out + ";\n"
+}
+
+fn column(driver: Driver, c: &Column) -> String {
+    let mut line = format!("{} {}", quote_ident(driver, &c.name), c.data_type);
+
+    match (driver, c.identity) {
+        (Driver::Postgres, Some(Identity::Always)) => line += " GENERATED ALWAYS AS IDENTITY",
+        (Driver::Postgres, Some(Identity::ByDefault)) => {
+            line += " GENERATED BY DEFAULT AS IDENTITY"
+        }
+        _ => {}
+    }
+    match (driver, c.generated.as_deref()) {
+        (_, None) => {}
+        (Driver::Sqlite, Some(_)) => line += " /* generated */",
+        (Driver::Postgres, Some(expr)) => line += &format!(" GENERATED ALWAYS AS ({expr}) STORED"),
+        (Driver::Mysql, Some(expr)) => line += &format!(" GENERATED ALWAYS AS ({expr})"),
+    }
+    if let Some(d) = &c.default {
+        line += &format!(" DEFAULT {d}");
+    }
+    if !c.nullable {
+        line += " NOT NULL";
+    }
+    if driver == Driver::Mysql {
+        if c.identity.is_some() {
+            line += " AUTO_INCREMENT";
+        }
+        if let Some(comment) = &c.comment {
+            line += &format!(" COMMENT {}", quote_literal(comment));
+        }
+    }
+    line
+}
+
+/// The object type as `COMMENT ON` and `ALTER … OWNER TO` spell it.
+fn kind_keyword(kind: RelationKind) -> &'static str {
+    match kind {
+        RelationKind::Table | RelationKind::PartitionedTable => "TABLE",
+        RelationKind::View => "VIEW",
+        RelationKind::MaterializedView => "MATERIALIZED VIEW",
+        RelationKind::ForeignTable => "FOREIGN TABLE",
+    }
+}
+
+fn quote_ident(driver: Driver, s: &str) -> String {
+    match driver {
+        Driver::Mysql => format!("`{}`", s.replace('`', "``")),
+        Driver::Postgres | Driver::Sqlite => format!("\"{}\"", s.replace('"', "\"\"")),
+    }
+}
+
+fn quote_literal(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::catalog::{CheckConstraint, ForeignKey, Index, PrimaryKey};
+
+    fn column(name: &str, data_type: &str, nullable: bool) -> Column {
+        Column {
+            name: name.into(),
+            data_type: data_type.into(),
+            nullable,
+            default: None,
+            identity: None,
+            generated: None,
+            comment: None,
+        }
+    }
+
+    fn orders() -> Relation {
+        let mut rel = Relation::new("orders".into(), RelationKind::Table);
+        let mut id = column("id", "integer", false);
+        id.identity = Some(Identity::Always);
+        let mut total = column("total", "numeric(10,2)", true);
+        total.default = Some("0".into());
+        total.comment = Some("Gross, in cents' worth".into());
+        rel.columns = vec![id, column("customer_id", "integer", false), total];
+        rel.primary_key = Some(PrimaryKey {
+            name: Some("orders_pkey".into()),
+            columns: vec!["id".into()],
+        });
+        rel.foreign_keys = vec![ForeignKey {
+            name: Some("orders_customer_fk".into()),
+            columns: vec!["customer_id".into()],
+            ref_schema: "public".into(),
+            ref_table: "customers".into(),
+            ref_columns: vec!["id".into()],
+            on_update: "NO ACTION".into(),
+            on_delete: "CASCADE".into(),
+        }];
+        rel.checks = vec![CheckConstraint {
+            name: Some("orders_total_check".into()),
+            expression: "total >= 0".into(),
+        }];
+        rel.indexes = vec![
+            Index {
+                name: "orders_pkey".into(),
+                columns: vec!["id".into()],
+                unique: true,
+                primary: true,
+                predicate: None,
+                definition: Some("CREATE UNIQUE INDEX orders_pkey ON orders (id)".into()),
+            },
+            Index {
+                name: "orders_customer_idx".into(),
+                columns: vec!["customer_id".into()],
+                unique: false,
+                primary: false,
+                predicate: None,
+                definition: Some("CREATE INDEX orders_customer_idx ON orders (customer_id)".into()),
+            },
+        ];
+        rel.owner = Some("app".into());
+        rel
+    }
+
+    #[test]
+    fn rebuilds_a_postgres_table() {
+        let ddl = create_statements(Driver::Postgres, "public", &orders());
+        assert_eq!(
+            ddl,
+            r#"CREATE TABLE "public"."orders" (
+    "id" integer GENERATED ALWAYS AS IDENTITY NOT NULL,
+    "customer_id" integer NOT NULL,
+    "total" numeric(10,2) DEFAULT 0,
+    CONSTRAINT "orders_pkey" PRIMARY KEY ("id"),
+    CONSTRAINT "orders_customer_fk" FOREIGN KEY ("customer_id") REFERENCES "public"."customers" ("id") ON DELETE CASCADE,
+    CONSTRAINT "orders_total_check" CHECK (total >= 0)
+);
+
+CREATE INDEX orders_customer_idx ON orders (customer_id);
+
+COMMENT ON COLUMN "public"."orders"."total" IS 'Gross, in cents'' worth';
+
+ALTER TABLE "public"."orders" OWNER TO "app";
+"#
+        );
+    }
+
+    #[test]
+    fn keeps_mysql_indexes_and_comments_inline() {
+        let ddl = create_statements(Driver::Mysql, "shop", &orders());
+        assert!(ddl.starts_with("CREATE TABLE `shop`.`orders` (\n"));
+        assert!(ddl.contains("`id` integer NOT NULL AUTO_INCREMENT,"));
+        assert!(ddl.contains("COMMENT 'Gross, in cents'' worth'"));
+        assert!(ddl.contains("    PRIMARY KEY (`id`),"));
+        assert!(ddl.contains("    KEY `orders_customer_idx` (`customer_id`),"));
+        assert!(!ddl.contains("CREATE INDEX"));
+        assert!(!ddl.contains("OWNER TO"));
+    }
+
+    #[test]
+    fn rebuilds_a_view_from_its_query() {
+        let mut rel = Relation::new("big_orders".into(), RelationKind::MaterializedView);
+        rel.view_query = Some(" SELECT * FROM orders WHERE total > 100;\n".into());
+        assert_eq!(
+            create_statements(Driver::Postgres, "public", &rel),
+            "CREATE MATERIALIZED VIEW \"public\".\"big_orders\" AS\nSELECT * FROM orders WHERE total > 100;\n"
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use bigdecimal::BigDecimal;
+use sqlx::mysql::{MySqlColumn, MySqlRow};
+use sqlx::postgres::{PgColumn, PgRow};
+use sqlx::sqlite::{SqliteColumn, SqliteRow};
+use sqlx::{Column, Decode, Row, Type, TypeInfo, ValueRef};
+
+use crate::model::value::{CellValue, ColumnKind, ColumnMeta};
+
+/// Decodes `Option<T>` and maps it through `f`; NULL and decode errors are handled here
+/// so the per-type arms below stay one line each.
+fn get<'r, R, T>(row: &'r R, i: usize, type_name: &str, f: impl FnOnce(T) -> CellValue) -> CellValue
+where
+    R: Row,
+    usize: sqlx::ColumnIndex<R>,
+    T: Decode<'r, R::Database> + Type<R::Database>,
+{
+    match row.try_get::<Option<T>, _>(i) {
+        Ok(Some(v)) => f(v),
+        Ok(None) => CellValue::Null,
+        Err(_) => CellValue::Unsupported(type_name.to_string()),
+    }
+}
+
+/// Drops the padding zeros left by the wire format and avoids exponent notation.
+fn numeric(v: BigDecimal) -> CellValue {
+    CellValue::Numeric(v.normalized().to_plain_string())
+}
+
+pub fn pg_column_meta(col: &PgColumn) -> ColumnMeta {
+    let type_name = col.type_info().name();
+    let kind = match type_name {
+        "BOOL" => ColumnKind::Bool,
+        "INT2" | "INT4" | "INT8" | "OID" => ColumnKind::Integer,
+        "FLOAT4" | "FLOAT8" => ColumnKind::Float,
+        "NUMERIC" | "MONEY" => ColumnKind::Numeric,
+        "TEXT" | "VARCHAR" | "BPCHAR" | "NAME" | "CHAR" => ColumnKind::Text,
+        "JSON" | "JSONB" => ColumnKind::Json,
+        "BYTEA" => ColumnKind::Binary,
+        "UUID" => ColumnKind::Uuid,
+        "TIMESTAMPTZ" | "TIMESTAMP" | "DATE" | "TIME" | "TIMETZ" | "INTERVAL" => {
+            ColumnKind::Temporal
+        }
+        _ => ColumnKind::Other,
+    };
+    ColumnMeta {
+        name: col.name().to_string(),
+        type_name: type_name.to_string(),
+        kind,
+    }
+}
+
+pub fn pg_cell_value(row: &PgRow, i: usize) -> CellValue {
+    let type_name = row.columns()[i].type_info().name();
+    match type_name {
+        "TEXT" | "VARCHAR" | "BPCHAR" | "NAME" => get(row, i, type_name, CellValue::Text),
+        "INT2" => get(row, i, type_name, |v: i16| CellValue::Int(v.into())),
+        "INT4" => get(row, i, type_name, |v: i32| CellValue::Int(v.into())),
+        "INT8" => get(row, i, type_name, CellValue::Int),
+        "FLOAT4" => get(row, i, type_name, |v: f32| CellValue::Float(v.into())),
+        "FLOAT8" => get(row, i, type_name, CellValue::Float),
+        "NUMERIC" => get(row, i, type_name, numeric),
+        "BOOL" => get(row, i, type_name, CellValue::Bool),
+        "UUID" => get(row, i, type_name, CellValue::Uuid),
+        "JSON" | "JSONB" => get(row, i, type_name, CellValue::Json),
+        "TIMESTAMPTZ" => get(row, i, type_name, CellValue::TimestampTz),
+        "TIMESTAMP" => get(row, i, type_name, CellValue::Timestamp),
+        "DATE" => get(row, i, type_name, CellValue::Date),
+        "TIME" => get(row, i, type_name, CellValue::Time),
+        "BYTEA" => get(row, i, type_name, CellValue::Bytes),
+        _ => get(row, i, type_name, CellValue::Text),
+    }
+}
+
+pub fn sqlite_column_meta(col: &SqliteColumn) -> ColumnMeta {
+    // Declared type; expressions without one report NULL.
+    let type_name = col.type_info().name();
+    let kind = match type_name {
+        "BOOLEAN" => ColumnKind::Bool,
+        "INTEGER" => ColumnKind::Integer,
+        "REAL" => ColumnKind::Float,
+        "NUMERIC" => ColumnKind::Numeric,
+        "TEXT" => ColumnKind::Text,
+        "BLOB" => ColumnKind::Binary,
+        "DATE" | "TIME" | "DATETIME" => ColumnKind::Temporal,
+        _ => ColumnKind::Other,
+    };
+    ColumnMeta {
+        name: col.name().to_string(),
+        type_name: type_name.to_string(),
+        kind,
+    }
+}
+
+/// SQLite values are dynamically typed, so dispatch on the storage class
+/// of the value itself rather than the declared column type.
+pub fn sqlite_cell_value(row: &SqliteRow, i: usize) -> CellValue {
+    let type_name = match row.try_get_raw(i) {
+        Ok(v) if v.is_null() => return CellValue::Null,
+        Ok(v) => v.type_info().name().to_string(),
+        Err(_) => return CellValue::Unsupported("?".into()),
+    };
+
+    match type_name.as_str() {
+        "INTEGER" | "BOOLEAN" => get(row, i, &type_name, CellValue::Int),
+        "REAL" => get(row, i, &type_name, CellValue::Float),
+        "BLOB" => get(row, i, &type_name, CellValue::Bytes),
+        _ => get(row, i, &type_name, CellValue::Text),
+    }
+}
+
+pub fn mysql_column_meta(col: &MySqlColumn) -> ColumnMeta {
+    let type_name = col.type_info().name();
+    let kind = match type_name {
+        "BOOLEAN" => ColumnKind::Bool,
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" | "YEAR" => ColumnKind::Integer,
+        t if t.ends_with(" UNSIGNED") => ColumnKind::Integer,
+        "FLOAT" | "DOUBLE" => ColumnKind::Float,
+        "DECIMAL" => ColumnKind::Numeric,
+        "CHAR" | "VARCHAR" | "TINYTEXT" | "TEXT" | "MEDIUMTEXT" | "LONGTEXT" | "ENUM" | "SET" => {
+            ColumnKind::Text
+        }
+        "JSON" => ColumnKind::Json,
+        "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BIT" => {
+            ColumnKind::Binary
+        }
+        "DATE" | "TIME" | "DATETIME" | "TIMESTAMP" => ColumnKind::Temporal,
+        _ => ColumnKind::Other,
+    };
+    ColumnMeta {
+        name: col.name().to_string(),
+        type_name: type_name.to_string(),
+        kind,
+    }
+}
+
+pub fn mysql_cell_value(row: &MySqlRow, i: usize) -> CellValue {
+    let type_name = row.columns()[i].type_info().name();
+    match type_name {
+        "BOOLEAN" => get(row, i, type_name, CellValue::Bool),
+
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" => {
+            get(row, i, type_name, CellValue::Int)
+        }
+
+        "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "MEDIUMINT UNSIGNED" | "INT UNSIGNED"
+        | "BIGINT UNSIGNED" => get(row, i, type_name, CellValue::UInt),
+
+        "FLOAT" | "DOUBLE" => get(row, i, type_name, CellValue::Float),
+
+        "DECIMAL" => get(row, i, type_name, numeric),
+
+        "JSON" => get(row, i, type_name, CellValue::Json),
+
+        // MySQL has no time zone aware type; both map to a naive timestamp.
+        "DATETIME" | "TIMESTAMP" => get(row, i, type_name, CellValue::Timestamp),
+        "DATE" => get(row, i, type_name, CellValue::Date),
+        "TIME" => get(row, i, type_name, CellValue::Time),
+
+        "YEAR" => match row.try_get_unchecked::<Option<u16>, _>(i) {
+            Ok(Some(v)) => CellValue::Int(v.into()),
+            Ok(None) => CellValue::Null,
+            Err(_) => CellValue::Unsupported(type_name.to_string()),
+        },
+
+        "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BIT" => {
+            get(row, i, type_name, CellValue::Bytes)
+        }
+
+        _ => match row.try_get_unchecked::<Option<String>, _>(i) {
+            Ok(Some(v)) => CellValue::Text(v),
+            Ok(None) => CellValue::Null,
+            Err(_) => CellValue::Unsupported(type_name.to_string()),
+        },
+    }
+}
@@ -0,0 +1,203 @@
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+
+use crate::model::plan::{Buffers, Plan, PlanNode};
+
+/// The statement that asks Postgres for `sql`'s plan as JSON.
+/// With `analyze` the statement really runs.
+pub fn explain_statement(sql: &str, analyze: bool, buffers: bool) -> String {
+    let mut options = vec!["FORMAT JSON"];
+    if analyze {
+        options.push("ANALYZE");
+    }
+    if buffers {
+        options.push("BUFFERS");
+    }
+    format!(
+        "EXPLAIN ({}) {}",
+        options.join(", "),
+        sql.trim_end_matches(';')
+    )
+}
+
+/// Reads the output of `EXPLAIN (FORMAT JSON)`: a one-element array holding
+/// the root `Plan` and the planning and execution times.
+pub fn parse_plan(json: &Value) -> Result<Plan> {
+    let top = match json {
+        Value::Array(items) => items.first().context("EXPLAIN returned an empty plan")?,
+        other => other,
+    };
+    let Some(root) = top.get("Plan") else {
+        bail!("EXPLAIN output has no \"Plan\"");
+    };
+    let root = parse_node(root);
+
+    Ok(Plan {
+        analyzed: root.actual_loops.is_some(),
+        root,
+        planning_ms: top.get("Planning Time").and_then(Value::as_f64),
+        execution_ms: top.get("Execution Time").and_then(Value::as_f64),
+    })
+}
+
+fn parse_node(node: &Value) -> PlanNode {
+    let text = |key: &str| node.get(key).and_then(Value::as_str);
+    let num = |key: &str| node.get(key).and_then(Value::as_f64);
+
+    PlanNode {
+        node_type: node_type(
+            text("Node Type").unwrap_or("?"),
+            text("Join Type"),
+            text("Strategy"),
+        ),
+        detail: detail(node),
+        startup_cost: num("Startup Cost").unwrap_or_default(),
+        total_cost: num("Total Cost").unwrap_or_default(),
+        plan_rows: num("Plan Rows").unwrap_or_default(),
+        actual_rows: num("Actual Rows"),
+        actual_loops: num("Actual Loops"),
+        actual_total_ms: num("Actual Total Time"),
+        buffers: buffers(node),
+        children: node
+            .get("Plans")
+            .and_then(Value::as_array)
+            .map(|plans| plans.iter().map(parse_node).collect())
+            .unwrap_or_default(),
+    }
+}
+
+/// Spells the node the way text EXPLAIN does: `Hash Left Join`,
+/// `HashAggregate`, `Nested Loop Anti Join`.
+fn node_type(name: &str, join: Option<&str>, strategy: Option<&str>) -> String {
+    match (name, join, strategy) {
+        (_, Some(join), _) if join != "Inner" => {
+            format!("{} {join} Join", name.trim_end_matches(" Join"))
+        }
+        ("Aggregate", _, Some("Hashed")) => "HashAggregate".to_string(),
+        ("Aggregate", _, Some("Sorted")) => "GroupAggregate".to_string(),
+        ("Aggregate", _, Some("Mixed")) => "MixedAggregate".to_string(),
+        ("SetOp", _, Some("Hashed")) => "HashSetOp".to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// `using <index> on <relation> <alias>`, `on <cte>`, the subplan name, …
+fn detail(node: &Value) -> Option<String> {
+    let text = |key: &str| node.get(key).and_then(Value::as_str);
+    let mut parts = Vec::new();
+
+    if let Some(sub) = text("Subplan Name") {
+        parts.push(sub.to_string());
+    }
+    if let Some(index) = text("Index Name") {
+        parts.push(format!("using {index}"));
+    }
+    let target = text("Relation Name")
+        .or_else(|| text("CTE Name"))
+        .or_else(|| text("Function Name"));
+    if let Some(target) = target {
+        let mut on = format!("on {target}");
+        if let Some(alias) = text("Alias").filter(|a| *a != target) {
+            on = format!("{on} {alias}");
+        }
+        parts.push(on);
+    }
+
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+fn buffers(node: &Value) -> Option<Buffers> {
+    let count = |key: &str| node.get(key).and_then(Value::as_i64);
+    // Present, even as zero, exactly when BUFFERS was asked for.
+    count("Shared Hit Blocks")?;
+    Some(Buffers {
+        shared_hit: count("Shared Hit Blocks").unwrap_or_default(),
+        shared_read: count("Shared Read Blocks").unwrap_or_default(),
+        temp_read: count("Temp Read Blocks").unwrap_or_default(),
+        temp_written: count("Temp Written Blocks").unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn adds_options_and_drops_the_semicolon() {
+        assert_eq!(
+            explain_statement("select 1;", false, false),
+            "EXPLAIN (FORMAT JSON) select 1"
+        );
+        assert_eq!(
+            explain_statement("select 1", true, true),
+            "EXPLAIN (FORMAT JSON, ANALYZE, BUFFERS) select 1"
+        );
+    }
+
+    #[test]
+    fn reads_nodes_times_and_buffers() {
+        let json = json!([{
+            "Plan": {
+                "Node Type": "Hash Join",
+                "Join Type": "Left",
+                "Startup Cost": 1.5,
+                "Total Cost": 10.0,
+                "Plan Rows": 100,
+                "Actual Rows": 90,
+                "Actual Loops": 1,
+                "Actual Total Time": 2.5,
+                "Shared Hit Blocks": 3,
+                "Shared Read Blocks": 1,
+                "Temp Read Blocks": 0,
+                "Temp Written Blocks": 0,
+                "Plans": [
+                    {
+                        "Node Type": "Index Scan",
+                        "Index Name": "orders_pkey",
+                        "Relation Name": "orders",
+                        "Alias": "o",
+                        "Total Cost": 5.0,
+                        "Plan Rows": 100
+                    },
+                    {"Node Type": "Aggregate", "Strategy": "Hashed", "Plan Rows": 1}
+                ]
+            },
+            "Planning Time": 0.2,
+            "Execution Time": 3.1
+        }]);
+        let plan = parse_plan(&json).unwrap();
+
+        assert!(plan.analyzed);
+        assert_eq!(plan.planning_ms, Some(0.2));
+        assert_eq!(plan.execution_ms, Some(3.1));
+        let root = &plan.root;
+        assert_eq!(root.node_type, "Hash Left Join");
+        assert_eq!((root.startup_cost, root.total_cost), (1.5, 10.0));
+        assert_eq!(root.actual_rows, Some(90.0));
+        let buffers = Buffers {
+            shared_hit: 3,
+            shared_read: 1,
+            temp_read: 0,
+            temp_written: 0,
+        };
+        assert_eq!(root.buffers, Some(buffers));
+
+        let [scan, agg] = root.children.as_slice() else {
+            panic!("expected two children");
+        };
+        assert_eq!(
+            scan.detail.as_deref(),
+            Some("using orders_pkey on orders o")
+        );
+        assert_eq!(scan.buffers, None);
+        assert_eq!(agg.node_type, "HashAggregate");
+        assert_eq!(agg.detail, None);
+    }
+
+    #[test]
+    fn rejects_output_without_a_plan() {
+        assert!(parse_plan(&json!([])).is_err());
+        assert!(parse_plan(&json!([{"Planning Time": 0.1}])).is_err());
+    }
+}
@@ -1 +1,7 @@
+pub mod conninfo;
+pub mod ddl;
+pub mod decode;
+pub mod explain;
+pub mod params;
+pub mod statement;
 pub mod value_fmt;
@@ -0,0 +1,199 @@
+use crate::model::connection::Driver;
+use crate::model::value::QueryParam;
+use crate::util::statement::{
+    is_line_comment, skip_block_comment, skip_dollar_quoted, skip_line, skip_quoted,
+    split_statements,
+};
+
+/// A `$1`, `:name` or `?` placeholder in a statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    /// `1` for `$1`, `name` for `:name`, and `1` for the first `?`.
+    pub name: String,
+    /// Character range of the placeholder, sigil included.
+    pub start: usize,
+    pub end: usize,
+}
+
+/// The `$1` and `:name` placeholders of `sql`, in order, and on MySQL and
+/// SQLite each bare `?`, numbered by position. Those inside literals, quoted
+/// identifiers and comments, as `driver` reads them, do not count, and
+/// neither do `::type` casts or `:=` named arguments.
+pub fn placeholders(sql: &str, driver: Driver) -> Vec<Placeholder> {
+    let chars: Vec<char> = sql.chars().collect();
+    let word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut out = Vec::new();
+    let mut questions = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let after_word = i > 0 && (word(chars[i - 1]) || chars[i - 1] == '$');
+        match c {
+            _ if is_line_comment(&chars, i, driver) => i = skip_line(&chars, i),
+            '/' if chars.get(i + 1) == Some(&'*') => i = skip_block_comment(&chars, i, driver),
+            '\'' | '"' | '`' => i = skip_quoted(&chars, i, c, driver),
+            '$' if !after_word && chars.get(i + 1).is_some_and(char::is_ascii_digit) => {
+                let end = (i + 1..chars.len())
+                    .find(|&j| !chars[j].is_ascii_digit())
+                    .unwrap_or(chars.len());
+                out.push(Placeholder {
+                    name: chars[i + 1..end].iter().collect(),
+                    start: i,
+                    end,
+                });
+                i = end;
+            }
+            '$' => i = skip_dollar_quoted(&chars, i, driver),
+            ':' if chars.get(i + 1) == Some(&':') => i += 2,
+            ':' if !after_word
+                && chars
+                    .get(i + 1)
+                    .is_some_and(|c| c.is_alphabetic() || *c == '_') =>
+            {
+                let end = (i + 1..chars.len())
+                    .find(|&j| !word(chars[j]))
+                    .unwrap_or(chars.len());
+                out.push(Placeholder {
+                    name: chars[i + 1..end].iter().collect(),
+                    start: i,
+                    end,
+                });
+                i = end;
+            }
+            // Postgres spells JSON operators with `?`; SQLite's `?1` is left alone.
+            '?' if driver != Driver::Postgres
+                && !chars.get(i + 1).is_some_and(char::is_ascii_digit) =>
+            {
+                questions += 1;
+                out.push(Placeholder {
+                    name: questions.to_string(),
+                    start: i,
+                    end: i + 1,
+                });
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+
+    out
+}
+
+/// Names of the placeholders in `sql`, each once, in order of first use.
+/// `?` are numbered within each statement, as they are bound.
+pub fn param_names(sql: &str, driver: Driver) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for stmt in split_statements(sql, driver) {
+        for p in placeholders(&stmt.sql, driver) {
+            if !names.contains(&p.name) {
+                names.push(p.name);
+            }
+        }
+    }
+    names
+}
+
+/// `sql` with its placeholders rewritten to what `driver` binds, and the
+/// placeholder name behind each bound position.
+///
+/// Postgres gets `$1`, `$2`, … by first use, each name bound once. Values
+/// are bound as text, so a placeholder whose `params` entry has a type is
+/// cast to it (`$1::text::integer`). MySQL and SQLite get a `?` per
+/// occurrence, and the same name is bound again each time.
+pub fn positional_sql(sql: &str, driver: Driver, params: &[QueryParam]) -> (String, Vec<String>) {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut out = String::with_capacity(sql.len());
+    let mut names: Vec<String> = Vec::new();
+    let mut last = 0;
+
+    for p in placeholders(sql, driver) {
+        out.extend(&chars[last..p.start]);
+        last = p.end;
+
+        if driver != Driver::Postgres {
+            out.push('?');
+            names.push(p.name);
+            continue;
+        }
+        let n = match names.iter().position(|n| *n == p.name) {
+            Some(i) => i + 1,
+            None => {
+                names.push(p.name.clone());
+                names.len()
+            }
+        };
+        out += &format!("${n}");
+        let ty = params
+            .iter()
+            .find(|q| q.name == p.name)
+            .and_then(|q| q.type_name.as_deref());
+        if let Some(ty) = ty {
+            out += &format!("::text::{ty}");
+        }
+    }
+    out.extend(&chars[last..]);
+
+    (out, names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_placeholders_outside_literals_and_casts() {
+        let sql = "select $1, :name, a::int, '$2 :x' from t where b := 1 -- :y";
+        assert_eq!(param_names(sql, Driver::Postgres), ["1", "name"]);
+    }
+
+    #[test]
+    fn skips_placeholders_in_escaped_strings() {
+        assert_eq!(param_names(r"select 'it\'s :x', :y", Driver::Mysql), ["y"]);
+        assert_eq!(
+            param_names(r"select E'it\'s :x', :y", Driver::Postgres),
+            ["y"]
+        );
+    }
+
+    #[test]
+    fn numbers_postgres_placeholders_by_first_use() {
+        let params = [QueryParam {
+            name: "id".into(),
+            type_name: Some("integer".into()),
+            value: Some("7".into()),
+        }];
+        let (sql, names) = positional_sql(
+            "select :id, $2, :name where x = :id",
+            Driver::Postgres,
+            &params,
+        );
+        assert_eq!(
+            sql,
+            "select $1::text::integer, $2, $3 where x = $1::text::integer"
+        );
+        assert_eq!(names, ["id", "2", "name"]);
+    }
+
+    #[test]
+    fn binds_each_question_mark_again() {
+        let (sql, names) = positional_sql("select :a, ':b', :a", Driver::Mysql, &[]);
+        assert_eq!(sql, "select ?, ':b', ?");
+        assert_eq!(names, ["a", "a"]);
+    }
+
+    #[test]
+    fn numbers_bare_question_marks() {
+        let sql = "select ?, '?', `?` /* ? */, :a, ? -- ?";
+        let (out, names) = positional_sql(sql, Driver::Mysql, &[]);
+        assert_eq!(out, "select ?, '?', `?` /* ? */, ?, ? -- ?");
+        assert_eq!(names, ["1", "a", "2"]);
+
+        assert_eq!(
+            param_names("select ?; select ?, ?", Driver::Sqlite),
+            ["1", "2"]
+        );
+        assert!(param_names("select ?1", Driver::Sqlite).is_empty());
+        assert!(param_names("select data ? 'k'", Driver::Postgres).is_empty());
+    }
+}
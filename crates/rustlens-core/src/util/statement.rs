@@ -0,0 +1,627 @@
+use crate::model::connection::Driver;
+
+/// Keywords that sit between CREATE/DROP/ALTER and the object type
+/// (`CREATE OR REPLACE VIEW`, `CREATE UNIQUE INDEX`, …) and are not part of the tag.
+const OBJECT_MODIFIERS: &[&str] = &[
+    "OR",
+    "REPLACE",
+    "TEMP",
+    "TEMPORARY",
+    "UNIQUE",
+    "UNLOGGED",
+    "GLOBAL",
+    "LOCAL",
+    "RECURSIVE",
+    "TRUSTED",
+    "PROCEDURAL",
+];
+
+/// Object types spelled with two words.
+const TWO_WORD_OBJECTS: &[&str] = &["MATERIALIZED", "FOREIGN", "EVENT", "ACCESS", "TEXT"];
+
+/// The command tag Postgres would report for `sql` (`INSERT 0 5`, `UPDATE 3`,
+/// `CREATE TABLE`), derived from its leading keywords since the drivers only
+/// hand back the row count. MySQL and SQLite have no OID to report, so an
+/// insert there is `INSERT 5`, and other statements that change rows are
+/// `5 rows affected`.
+pub fn command_tag(sql: &str, rows_affected: u64, driver: Driver) -> String {
+    let words = top_level_words(sql, driver);
+    let Some(verb) = words.first() else {
+        return "OK".to_string();
+    };
+
+    let verb = match verb.as_str() {
+        // The tag belongs to the statement after the CTEs.
+        "WITH" => words[1..]
+            .iter()
+            .find(|w| {
+                matches!(
+                    w.as_str(),
+                    "SELECT" | "INSERT" | "UPDATE" | "DELETE" | "MERGE"
+                )
+            })
+            .map_or("SELECT", String::as_str),
+        v => v,
+    };
+
+    match verb {
+        "INSERT" if driver == Driver::Postgres => format!("INSERT 0 {rows_affected}"),
+        "INSERT" => format!("INSERT {rows_affected}"),
+        "SELECT" | "VALUES" | "TABLE" => format!("SELECT {rows_affected}"),
+        "UPDATE" | "DELETE" | "MERGE" | "COPY" | "FETCH" | "MOVE" => {
+            format!("{verb} {rows_affected}")
+        }
+        "CREATE" | "DROP" | "ALTER" => {
+            let mut rest = words[1..]
+                .iter()
+                .map(String::as_str)
+                .skip_while(|w| OBJECT_MODIFIERS.contains(w));
+            match rest.next() {
+                Some(obj) if TWO_WORD_OBJECTS.contains(&obj) => match rest.next() {
+                    Some(next) => format!("{verb} {obj} {next}"),
+                    None => format!("{verb} {obj}"),
+                },
+                Some(obj) => format!("{verb} {obj}"),
+                None => verb.to_string(),
+            }
+        }
+        "TRUNCATE" => "TRUNCATE TABLE".to_string(),
+        "START" => "START TRANSACTION".to_string(),
+        _ if driver != Driver::Postgres && rows_affected > 0 => {
+            let rows = if rows_affected == 1 { "row" } else { "rows" };
+            format!("{rows_affected} {rows} affected")
+        }
+        v => v.to_string(),
+    }
+}
+
+/// Statements that open or close a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxControl {
+    Begin,
+    Commit,
+    Rollback,
+}
+
+/// Recognizes BEGIN / COMMIT / ROLLBACK and their synonyms, which the worker
+/// tracks to know the session's transaction state. Savepoints and two-phase commit
+/// (`ROLLBACK TO`, `COMMIT PREPARED`) are ordinary statements.
+pub fn transaction_control(sql: &str, driver: Driver) -> Option<TxControl> {
+    let words = top_level_words(sql, driver);
+    let second = words.get(1).map(String::as_str);
+    match words.first()?.as_str() {
+        "BEGIN" => Some(TxControl::Begin),
+        "START" if second == Some("TRANSACTION") => Some(TxControl::Begin),
+        "COMMIT" | "END" if second != Some("PREPARED") => Some(TxControl::Commit),
+        "ROLLBACK" | "ABORT" if !words.iter().any(|w| w == "TO" || w == "PREPARED") => {
+            Some(TxControl::Rollback)
+        }
+        _ => None,
+    }
+}
+
+/// A read-only query a cursor can be declared for: SELECT, VALUES, TABLE, or
+/// WITH … SELECT whose CTEs change nothing.
+pub fn is_query(sql: &str, driver: Driver) -> bool {
+    match top_level_words(sql, driver).first().map(String::as_str) {
+        Some("SELECT" | "VALUES" | "TABLE") => true,
+        Some("WITH") => !words(sql, false, driver)
+            .iter()
+            .any(|w| matches!(w.as_str(), "INSERT" | "UPDATE" | "DELETE" | "MERGE")),
+        _ => false,
+    }
+}
+
+/// Upper-cased bare words of `sql` outside comments, literals, quoted
+/// identifiers and parentheses.
+fn top_level_words(sql: &str, driver: Driver) -> Vec<String> {
+    words(sql, true, driver)
+}
+
+/// Upper-cased bare words of `sql` outside comments, literals and quoted
+/// identifiers; with `top_level`, only those outside parentheses.
+fn words(sql: &str, top_level: bool, driver: Driver) -> Vec<String> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut words = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            _ if is_line_comment(&chars, i, driver) => i = skip_line(&chars, i),
+            '/' if chars.get(i + 1) == Some(&'*') => i = skip_block_comment(&chars, i, driver),
+            '\'' | '"' | '`' => i = skip_quoted(&chars, i, c, driver),
+            '$' => i = skip_dollar_quoted(&chars, i, driver),
+            '(' => {
+                depth += 1;
+                i += 1;
+            }
+            ')' => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let (word, end) = word_at(&chars, i);
+                i = end;
+                if depth == 0 || !top_level {
+                    words.push(word);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    words
+}
+
+/// The upper-cased word starting at `i`, and the index after it.
+fn word_at(chars: &[char], i: usize) -> (String, usize) {
+    let end = (i..chars.len())
+        .find(|&j| !(chars[j].is_alphanumeric() || chars[j] == '_'))
+        .unwrap_or(chars.len());
+    (chars[i..end].iter().collect::<String>().to_uppercase(), end)
+}
+
+/// A `-- …` comment starts at `i`, or with MySQL a `# …` one.
+pub(crate) fn is_line_comment(chars: &[char], i: usize, driver: Driver) -> bool {
+    match chars[i] {
+        '-' => chars.get(i + 1) == Some(&'-'),
+        '#' => driver == Driver::Mysql,
+        _ => false,
+    }
+}
+
+/// Returns the index of the newline ending the line `i` is on.
+pub(crate) fn skip_line(chars: &[char], mut i: usize) -> usize {
+    while i < chars.len() && chars[i] != '\n' {
+        i += 1;
+    }
+    i
+}
+
+/// `i` is at `/*`; returns the index after the matching `*/`. Postgres nests
+/// these; MySQL and SQLite end one at the first `*/`.
+pub(crate) fn skip_block_comment(chars: &[char], mut i: usize, driver: Driver) -> usize {
+    let nests = driver == Driver::Postgres;
+    let mut depth = 0usize;
+    while i < chars.len() {
+        if chars[i] == '/' && chars.get(i + 1) == Some(&'*') && (nests || depth == 0) {
+            depth += 1;
+            i += 2;
+        } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                break;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    i
+}
+
+/// `i` is at the opening `quote`; a doubled quote is an escaped one. So is
+/// a backslashed one in MySQL strings and in Postgres `E'…'` strings.
+pub(crate) fn skip_quoted(chars: &[char], mut i: usize, quote: char, driver: Driver) -> usize {
+    let backslash = match driver {
+        Driver::Mysql => quote != '`',
+        Driver::Postgres => quote == '\'' && is_escape_string(chars, i),
+        Driver::Sqlite => false,
+    };
+    i += 1;
+    while i < chars.len() {
+        if backslash && chars[i] == '\\' {
+            i += 2;
+            continue;
+        }
+        if chars[i] == quote {
+            if chars.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    i
+}
+
+/// The quote at `i` opens an `E'…'` string: it follows a lone `E`.
+fn is_escape_string(chars: &[char], i: usize) -> bool {
+    let word = |c: &char| c.is_alphanumeric() || *c == '_' || *c == '$';
+    i > 0 && matches!(chars[i - 1], 'E' | 'e') && (i < 2 || !word(&chars[i - 2]))
+}
+
+/// `i` is at a `$`. Skips a Postgres `$tag$ … $tag$` body; anything else
+/// (`$1` parameters, a `$` inside a name, a stray `$`) is stepped over.
+pub(crate) fn skip_dollar_quoted(chars: &[char], i: usize, driver: Driver) -> usize {
+    let in_name = i > 0 && (chars[i - 1].is_alphanumeric() || chars[i - 1] == '_');
+    if driver != Driver::Postgres || in_name {
+        return i + 1;
+    }
+    let mut j = i + 1;
+    while j < chars.len() && (chars[j].is_alphanumeric() || chars[j] == '_') {
+        j += 1;
+    }
+    // `$1` is a parameter, and a tag cannot start with a digit.
+    if chars.get(j) != Some(&'$') || chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()) {
+        return i + 1;
+    }
+
+    let tag = &chars[i..=j];
+    let mut k = j + 1;
+    while k + tag.len() <= chars.len() {
+        if &chars[k..k + tag.len()] == tag {
+            return k + tag.len();
+        }
+        k += 1;
+    }
+    chars.len()
+}
+
+/// One statement of a script, as cut by `split_statements`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptStatement {
+    /// Trimmed text, without the terminating `;`.
+    pub sql: String,
+    /// Character offset of `sql` in the script, to map error positions back.
+    pub offset: usize,
+}
+
+/// Splits `script` at top-level `;`, reading literals and comments the way
+/// `driver` does. Semicolons inside literals, quoted identifiers, `$tag$`
+/// bodies, comments and parentheses do not count, nor do those in the
+/// `BEGIN … END` body of a function, procedure or trigger (Postgres
+/// `BEGIN ATOMIC`, MySQL procedures, SQLite triggers). Pieces holding
+/// nothing but comments are dropped.
+pub fn split_statements(script: &str, driver: Driver) -> Vec<ScriptStatement> {
+    let chars: Vec<char> = script.chars().collect();
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut has_code = false;
+    let mut parens = 0usize;
+    // The statement's first words, and how deep into its body a `;` is.
+    let mut lead: Vec<String> = Vec::new();
+    let mut has_body = false;
+    let mut body = 0usize;
+    let mut i = 0;
+
+    let mut push = |start: usize, end: usize, has_code: bool| {
+        if !has_code {
+            return;
+        }
+        let piece = &chars[start..end];
+        let lead = piece.iter().take_while(|c| c.is_whitespace()).count();
+        let sql: String = piece[lead..].iter().collect();
+        out.push(ScriptStatement {
+            sql: sql.trim_end().to_string(),
+            offset: start + lead,
+        });
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            _ if is_line_comment(&chars, i, driver) => i = skip_line(&chars, i),
+            '/' if chars.get(i + 1) == Some(&'*') => i = skip_block_comment(&chars, i, driver),
+            '\'' | '"' | '`' => {
+                has_code = true;
+                i = skip_quoted(&chars, i, c, driver);
+            }
+            '$' => {
+                has_code = true;
+                i = skip_dollar_quoted(&chars, i, driver);
+            }
+            ';' if parens == 0 && body == 0 => {
+                push(start, i, has_code);
+                has_code = false;
+                lead.clear();
+                has_body = false;
+                i += 1;
+                start = i;
+            }
+            '(' | ')' => {
+                has_code = true;
+                parens = match c {
+                    '(' => parens + 1,
+                    _ => parens.saturating_sub(1),
+                };
+                i += 1;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                has_code = true;
+                let (word, end) = word_at(&chars, i);
+                i = end;
+                if lead.len() < 6 {
+                    lead.push(word.clone());
+                    has_body = lead[0] == "CREATE"
+                        && lead
+                            .iter()
+                            .any(|w| matches!(w.as_str(), "FUNCTION" | "PROCEDURE" | "TRIGGER"));
+                }
+                if !has_body {
+                    continue;
+                }
+                match word.as_str() {
+                    "BEGIN" | "CASE" => body += 1,
+                    "END" => {
+                        let (next, next_end) = next_word(&chars, i);
+                        match next.as_str() {
+                            // MySQL's `END IF`, … close blocks that were not counted.
+                            "IF" | "LOOP" | "WHILE" | "REPEAT" => {}
+                            "CASE" => {
+                                body = body.saturating_sub(1);
+                                i = next_end;
+                            }
+                            _ => body = body.saturating_sub(1),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            c => {
+                has_code |= !c.is_whitespace();
+                i += 1;
+            }
+        }
+    }
+    push(start, chars.len(), has_code);
+
+    out
+}
+
+/// The word after the blanks following `i`, and the index after it;
+/// empty when something else comes first.
+fn next_word(chars: &[char], i: usize) -> (String, usize) {
+    let start = (i..chars.len())
+        .find(|&j| !chars[j].is_whitespace())
+        .unwrap_or(chars.len());
+    word_at(chars, start)
+}
+
+/// The statement of `script` that character `offset` falls in: the last one
+/// starting at or before it, or the first when it sits before them all.
+pub fn statement_at(script: &str, offset: usize, driver: Driver) -> Option<ScriptStatement> {
+    let statements = split_statements(script, driver);
+    let before = statements.iter().rposition(|s| s.offset <= offset);
+    statements.into_iter().nth(before.unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(script: &str, driver: Driver) -> Vec<String> {
+        split_statements(script, driver)
+            .into_iter()
+            .map(|s| s.sql)
+            .collect()
+    }
+
+    #[test]
+    fn splits_at_top_level_semicolons() {
+        let statements = split_statements("select 1;\n  select 2 ;", Driver::Postgres);
+        assert_eq!(
+            statements,
+            vec![
+                ScriptStatement {
+                    sql: "select 1".into(),
+                    offset: 0,
+                },
+                ScriptStatement {
+                    sql: "select 2".into(),
+                    offset: 12,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_pieces_that_are_only_comments() {
+        let script = "-- setup\n;select 1; /* a; b */ ;\n-- done";
+        assert_eq!(split(script, Driver::Postgres), ["select 1"]);
+    }
+
+    #[test]
+    fn keeps_semicolons_in_literals_and_identifiers() {
+        let script = r#"select 'a;''b', "x;y" from t; select 2"#;
+        assert_eq!(
+            split(script, Driver::Postgres),
+            [r#"select 'a;''b', "x;y" from t"#, "select 2"]
+        );
+        assert_eq!(
+            split("select `a;b` from t; select 2", Driver::Mysql),
+            ["select `a;b` from t", "select 2"]
+        );
+    }
+
+    #[test]
+    fn mysql_strings_take_backslash_escapes() {
+        let script = r#"insert into t values ('it\'s; fine', "a\";b"); select 2"#;
+        assert_eq!(
+            split(script, Driver::Mysql),
+            [
+                r#"insert into t values ('it\'s; fine', "a\";b")"#,
+                "select 2"
+            ]
+        );
+    }
+
+    #[test]
+    fn postgres_escapes_only_in_e_strings() {
+        assert_eq!(
+            split(r"select E'a\';b'; select 2", Driver::Postgres),
+            [r"select E'a\';b'", "select 2"]
+        );
+        // A standard string ends at the quote after the backslash.
+        assert_eq!(
+            split(r"select 'a\'; select 2", Driver::Postgres),
+            [r"select 'a\'", "select 2"]
+        );
+        // `some'…'` is not an E string.
+        assert_eq!(
+            split(r"select some'a\'; select 2", Driver::Postgres),
+            [r"select some'a\'", "select 2"]
+        );
+    }
+
+    #[test]
+    fn sqlite_has_no_backslash_escapes() {
+        assert_eq!(
+            split(r"select 'a\'; select 2", Driver::Sqlite),
+            [r"select 'a\'", "select 2"]
+        );
+    }
+
+    #[test]
+    fn dollar_quotes_are_postgres_only() {
+        let script = "do $body$ begin perform 1; end $body$; select 2";
+        assert_eq!(
+            split(script, Driver::Postgres),
+            ["do $body$ begin perform 1; end $body$", "select 2"]
+        );
+        assert_eq!(
+            split("select a$b$; select $1", Driver::Postgres),
+            ["select a$b$", "select $1"]
+        );
+        assert_eq!(
+            split("select $x$; select 2", Driver::Mysql),
+            ["select $x$", "select 2"]
+        );
+    }
+
+    #[test]
+    fn nests_block_comments_only_on_postgres() {
+        let script = "select 1 /* a /* b */ ; select 2 */; select 3";
+        assert_eq!(
+            split(script, Driver::Postgres),
+            ["select 1 /* a /* b */ ; select 2 */", "select 3"]
+        );
+        for driver in [Driver::Mysql, Driver::Sqlite] {
+            assert_eq!(
+                split(script, driver),
+                ["select 1 /* a /* b */", "select 2 */", "select 3"]
+            );
+        }
+    }
+
+    #[test]
+    fn mysql_hash_comments() {
+        let script = "select 1 # don't; split\n; select 2";
+        assert_eq!(
+            split(script, Driver::Mysql),
+            ["select 1 # don't; split", "select 2"]
+        );
+    }
+
+    #[test]
+    fn keeps_semicolons_in_parentheses() {
+        let script = "create rule r as on insert to t do also (insert into a values (1); insert into b values (2)); select 2";
+        assert_eq!(split(script, Driver::Postgres).len(), 2);
+    }
+
+    #[test]
+    fn keeps_begin_atomic_bodies_whole() {
+        let script = "create function f() returns int language sql\n\
+                      begin atomic\n  select case when true then 1 end;\n  select 2;\nend;\n\
+                      select f()";
+        let statements = split(script, Driver::Postgres);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].ends_with("end"));
+        assert_eq!(statements[1], "select f()");
+    }
+
+    #[test]
+    fn keeps_mysql_procedure_bodies_whole() {
+        let script = "create procedure p(x int)\nbegin\n  if x > 0 then select 1; end if;\n  \
+                      case x when 1 then select 2; end case;\n  \
+                      lbl: loop leave lbl; end loop;\nend;\ncall p(1)";
+        let statements = split(script, Driver::Mysql);
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[1], "call p(1)");
+    }
+
+    #[test]
+    fn keeps_sqlite_trigger_bodies_whole() {
+        let script = "create trigger tr after insert on t begin\n  \
+                      update c set n = n + 1; delete from q;\nend; select 1";
+        assert_eq!(split(script, Driver::Sqlite).len(), 2);
+    }
+
+    #[test]
+    fn statement_at_picks_the_statement_under_the_cursor() {
+        let script = "select 1; select 2; select 3";
+        let at = |offset| statement_at(script, offset, Driver::Postgres).map(|s| s.sql);
+        assert_eq!(at(0).as_deref(), Some("select 1"));
+        assert_eq!(at(12).as_deref(), Some("select 2"));
+        assert_eq!(at(100).as_deref(), Some("select 3"));
+        assert_eq!(statement_at("  -- nothing", 0, Driver::Postgres), None);
+    }
+
+    #[test]
+    fn command_tags() {
+        let tag = |sql, n| command_tag(sql, n, Driver::Postgres);
+        assert_eq!(tag("insert into t values (1)", 5), "INSERT 0 5");
+        assert_eq!(tag("update t set a = 1", 3), "UPDATE 3");
+        assert_eq!(tag("with x as (select 1) delete from t", 2), "DELETE 2");
+        assert_eq!(
+            tag("create or replace view v as select 1", 0),
+            "CREATE VIEW"
+        );
+        assert_eq!(
+            tag("create materialized view m as select 1", 0),
+            "CREATE MATERIALIZED VIEW"
+        );
+        assert_eq!(tag("create unique index i on t (a)", 0), "CREATE INDEX");
+        assert_eq!(tag("truncate t", 0), "TRUNCATE TABLE");
+        assert_eq!(tag("-- only a comment", 0), "OK");
+    }
+
+    #[test]
+    fn command_tags_on_mysql() {
+        let tag = |sql, n| command_tag(sql, n, Driver::Mysql);
+        assert_eq!(tag("insert into t values (1), (2)", 2), "INSERT 2");
+        assert_eq!(tag("update t set a = 1", 3), "UPDATE 3");
+        assert_eq!(tag("replace into t values (1)", 2), "2 rows affected");
+        assert_eq!(tag("set @a = 1", 0), "SET");
+    }
+
+    #[test]
+    fn command_tags_on_sqlite() {
+        let tag = |sql, n| command_tag(sql, n, Driver::Sqlite);
+        assert_eq!(tag("insert into t values (1)", 1), "INSERT 1");
+        assert_eq!(tag("delete from t", 4), "DELETE 4");
+        assert_eq!(tag("insert or replace into t values (1)", 1), "INSERT 1");
+        assert_eq!(tag("replace into t values (1)", 1), "1 row affected");
+    }
+
+    #[test]
+    fn recognizes_transaction_control() {
+        let tx = |sql| transaction_control(sql, Driver::Postgres);
+        assert_eq!(
+            tx("BEGIN ISOLATION LEVEL SERIALIZABLE"),
+            Some(TxControl::Begin)
+        );
+        assert_eq!(tx("start transaction"), Some(TxControl::Begin));
+        assert_eq!(tx("end"), Some(TxControl::Commit));
+        assert_eq!(tx("abort"), Some(TxControl::Rollback));
+        assert_eq!(tx("rollback to savepoint s"), None);
+        assert_eq!(tx("commit prepared 'x'"), None);
+        assert_eq!(tx("select 'begin'"), None);
+    }
+
+    #[test]
+    fn queries_a_cursor_can_read() {
+        let query = |sql| is_query(sql, Driver::Postgres);
+        assert!(query("select 1"));
+        assert!(query("values (1), (2)"));
+        assert!(query("with x as (select 1) select * from x"));
+        assert!(!query(
+            "with x as (delete from t returning *) select * from x"
+        ));
+        assert!(!query("insert into t select 1 returning *"));
+        assert!(!query("explain select 1"));
+    }
+}
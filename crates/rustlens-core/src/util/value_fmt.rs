@@ -1,87 +1,29 @@
-use sqlx::postgres::PgRow;
-use sqlx::{Column, Row, TypeInfo};
+use crate::model::value::CellValue;
 
-pub fn cell_to_string(row: &PgRow, i: usize) -> String {
-    let type_name = row.columns()[i].type_info().name();
-    match type_name {
-        "TEXT" | "VARCHAR" | "BPCHAR" | "NAME" => match row.try_get::<Option<String>, _>(i) {
-            Ok(Some(v)) => v,
-            Ok(None) => "NULL".into(),
-            Err(_) => "<error>".into(),
-        },
-
-        "INT2" | "INT4" | "INT8" => match row.try_get::<Option<i64>, _>(i) {
-            Ok(Some(v)) => v.to_string(),
-            Ok(None) => "NULL".into(),
-            Err(_) => "<error>".into(),
-        },
-
-        "FLOAT4" | "FLOAT8" => match row.try_get::<Option<f64>, _>(i) {
-            Ok(Some(v)) => v.to_string(),
-            Ok(None) => "NULL".into(),
-            Err(_) => "<error>".into(),
-        },
-
-        "BOOL" => match row.try_get::<Option<bool>, _>(i) {
-            Ok(Some(v)) => v.to_string(),
-            Ok(None) => "NULL".into(),
-            Err(_) => "<error>".into(),
-        },
-
-        "UUID" => match row.try_get::<Option<uuid::Uuid>, _>(i) {
-            Ok(Some(v)) => v.to_string(),
-            Ok(None) => "NULL".into(),
-            Err(_) => "<error>".into(),
-        },
-
-        "JSON" | "JSONB" => match row.try_get::<Option<serde_json::Value>, _>(i) {
-            Ok(Some(v)) => v.to_string(),
-            Ok(None) => "NULL".into(),
-            Err(_) => "<error>".into(),
-        },
-
-        "TIMESTAMPTZ" => match row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>(i) {
-            Ok(Some(v)) => v.to_rfc3339(),
-            Ok(None) => "NULL".into(),
-            Err(_) => "<error>".into(),
-        },
-
-        "TIMESTAMP" => match row.try_get::<Option<chrono::NaiveDateTime>, _>(i) {
-            Ok(Some(v)) => v.format("%Y-%m-%d %H:%M:%S").to_string(),
-            Ok(None) => "NULL".into(),
-            Err(_) => "<error>".into(),
-        },
-
-        "DATE" => match row.try_get::<Option<chrono::NaiveDate>, _>(i) {
-            Ok(Some(v)) => v.format("%Y-%m-%d").to_string(),
-            Ok(None) => "NULL".into(),
-            Err(_) => "<error>".into(),
-        },
-
-        "TIME" => match row.try_get::<Option<chrono::NaiveTime>, _>(i) {
-            Ok(Some(v)) => v.format("%H:%M:%S").to_string(),
-            Ok(None) => "NULL".into(),
-            Err(_) => "<error>".into(),
-        },
-
-        "BYTEA" => match row.try_get::<Option<Vec<u8>>, _>(i) {
-            Ok(Some(bytes)) => {
-                let preview = hex_preview(&bytes, 16);
-                if bytes.len() > 16 {
-                    format!("<bytea {}B {}…>", bytes.len(), preview)
-                } else {
-                    format!("<bytea {}B {}>", bytes.len(), preview)
-                }
+/// Renders a cell for display. Called by the UI at draw time.
+pub fn cell_to_string(v: &CellValue) -> String {
+    match v {
+        CellValue::Null => "NULL".into(),
+        CellValue::Bool(v) => v.to_string(),
+        CellValue::Int(v) => v.to_string(),
+        CellValue::UInt(v) => v.to_string(),
+        CellValue::Float(v) => v.to_string(),
+        CellValue::Numeric(v) | CellValue::Text(v) => v.clone(),
+        CellValue::Json(v) => v.to_string(),
+        CellValue::Uuid(v) => v.to_string(),
+        CellValue::TimestampTz(v) => v.to_rfc3339(),
+        CellValue::Timestamp(v) => v.format("%Y-%m-%d %H:%M:%S").to_string(),
+        CellValue::Date(v) => v.format("%Y-%m-%d").to_string(),
+        CellValue::Time(v) => v.format("%H:%M:%S").to_string(),
+        CellValue::Bytes(bytes) => {
+            let preview = hex_preview(bytes, 16);
+            if bytes.len() > 16 {
+                format!("<bytes {}B {}…>", bytes.len(), preview)
+            } else {
+                format!("<bytes {}B {}>", bytes.len(), preview)
             }
-            Ok(None) => "NULL".into(),
-            Err(_) => "<error>".into(),
-        },
-
-        _ => match row.try_get::<Option<String>, _>(i) {
-            Ok(Some(v)) => v,
-            Ok(None) => "NULL".into(),
-            Err(_) => format!("<{}>", type_name),
-        },
+        }
+        CellValue::Unsupported(type_name) => format!("<{}>", type_name),
     }
 }
 
@@ -94,3 +36,61 @@ fn hex_preview(bytes: &[u8], max: usize) -> String {
     }
     out
 }
+
+/// `1204` → `1,204`.
+pub fn format_count(n: i64) -> String {
+    let digits = n.unsigned_abs().to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3 + 1);
+    if n < 0 {
+        out.push('-');
+    }
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// A rounded count for estimates: `950`, `1.5k`, `240k`, `3.1M`.
+pub fn format_approx(n: i64) -> String {
+    let f = n as f64;
+    if n < 1_000 {
+        n.to_string()
+    } else if n < 10_000 {
+        format!("{:.1}k", f / 1e3)
+    } else if n < 1_000_000 {
+        format!("{:.0}k", f / 1e3)
+    } else if n < 10_000_000 {
+        format!("{:.1}M", f / 1e6)
+    } else if n < 1_000_000_000 {
+        format!("{:.0}M", f / 1e6)
+    } else {
+        format!("{:.1}B", f / 1e9)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_digits_in_threes() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(1204), "1,204");
+        assert_eq!(format_count(1_000_000), "1,000,000");
+        assert_eq!(format_count(-1_234_567), "-1,234,567");
+    }
+
+    #[test]
+    fn rounds_estimates_to_a_unit() {
+        assert_eq!(format_approx(950), "950");
+        assert_eq!(format_approx(1_500), "1.5k");
+        assert_eq!(format_approx(240_400), "240k");
+        assert_eq!(format_approx(3_140_000), "3.1M");
+        assert_eq!(format_approx(45_000_000), "45M");
+        assert_eq!(format_approx(2_500_000_000), "2.5B");
+    }
+}
@@ -4,8 +4,10 @@ pub enum NavDir {
     Down,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PageDir {
     Prev,
     Next,
+    First,
+    Last,
 }
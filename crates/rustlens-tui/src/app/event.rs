@@ -4,5 +4,5 @@ use rustlens_core::db;
 #[derive(Debug)]
 pub enum AppEvent {
     Input(term::input::UiEvent),
-    Db(db::DbEvt),
+    Db(Box<db::DbReply>),
 }
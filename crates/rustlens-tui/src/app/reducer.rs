@@ -1,49 +1,116 @@
-use tokio::sync::mpsc;
+use std::time::Instant;
 
-use rustlens_core::db;
+use rustlens_core::db::{self, Pane};
+use rustlens_core::model::catalog::{Relation, RelationKind};
+use rustlens_core::model::paging::{PageCursor, PageNav, PagePos};
+use rustlens_core::model::value::QueryParam;
+use rustlens_core::util::params::param_names;
+use rustlens_core::util::statement::statement_at;
+use rustlens_core::util::value_fmt::format_count;
 
 use crate::app::actions::{NavDir, PageDir};
 use crate::app::event::AppEvent;
 use crate::app::sql::complete;
-use crate::app::state::Mode;
-use crate::app::state::{Focus, RootState, Tab};
+use crate::app::state::{DdlView, Mode, ParamForm, PlanView, StatementLog};
+use crate::app::state::{Focus, RootState, SessionState, Tab};
 use crate::term::input::UiEvent;
 
-pub async fn reduce(
-    root: &mut RootState,
-    ev: AppEvent,
-    db_cmd_tx: &mpsc::Sender<db::DbCmd>,
-) -> bool {
+pub async fn reduce(root: &mut RootState, ev: AppEvent, db_cmd_tx: &db::DbClient) -> bool {
     match ev {
-        AppEvent::Db(evt) => {
-            handle_db(root, evt, db_cmd_tx).await;
-            false
-        }
+        AppEvent::Db(reply) => handle_db(root, *reply, db_cmd_tx).await,
         AppEvent::Input(evt) => handle_input(root, evt, db_cmd_tx).await,
     }
 }
 
-async fn handle_db(root: &mut RootState, evt: db::DbEvt, db_cmd_tx: &mpsc::Sender<db::DbCmd>) {
+/// Whether `evt` answers a request since replaced by a newer one of the
+/// same kind, like a page the user has already paged past.
+fn superseded(s: &SessionState, id: db::RequestId, evt: &db::DbEvt) -> bool {
+    use db::DbEvt::*;
+    let r = &s.requests;
+    let latest = match evt {
+        // What the session is doing now, whichever request it came with.
+        Status(_) | TxStatus(_) | TxDone { .. } => return false,
+        // An error can answer any kind of request.
+        Error(_) => return !r.is_latest(id),
+        TablesLoaded { .. } | SqlMetaLoaded { .. } => r.tables,
+        SchemasLoaded { .. } => r.schemas,
+        QueryResult { .. } => r.page,
+        // Estimates come with the first page, exact counts on request.
+        RowCount { count, .. } if !count.exact => r.page,
+        RowCount { .. } | CountCancelled => r.count,
+        Ddl { .. } => r.ddl,
+        MaterializedViewRefreshed { .. } => r.refresh,
+        RowBatch { .. }
+        | StatementDone { .. }
+        | ScriptDone { .. }
+        | PlanLoaded { .. }
+        | ExplainCancelled
+        | ParamsDescribed { .. } => r.sql,
+    };
+    id < latest
+}
+
+/// Returns true when the app should quit.
+async fn handle_db(root: &mut RootState, reply: db::DbReply, db_cmd_tx: &db::DbClient) -> bool {
+    let db::DbReply { id, pane, evt } = reply;
+    if superseded(&root.session, id, &evt) {
+        return false;
+    }
+
     match evt {
         db::DbEvt::Status(msg) => root.status.left = msg,
 
+        db::DbEvt::TxStatus(tx) => root.session.tx_status = tx,
+
+        db::DbEvt::TxDone { tag } => {
+            root.status.right = tag;
+            if root.session.quit_after_tx {
+                return true;
+            }
+        }
+
         db::DbEvt::Error(e) => {
+            // Only what the failed request ran has stopped.
+            let s = &mut root.session;
+            if id == s.requests.sql {
+                s.sql_running_since = None;
+            }
+            if id == s.requests.count {
+                s.counting = false;
+            }
+            // The commit/rollback chosen on quit failed; stay and show why.
+            root.session.quit_after_tx = false;
             // #[cfg(debug_assertions)]
             // eprintln!("[tui] DbEvt::Error: {}", e);
-            if looks_like_missing_schema(&e) && root.session.schema != "public" {
-                let bad = root.session.schema.clone();
-                // #[cfg(debug_assertions)]
-                // eprintln!("[tui] fallback schema '{}' -> 'public'", bad);
-                root.session.schema = "public".to_string();
-
-                root.status.left = format!("Schema '{bad}' not found. Using public schema.");
-
-                let schema = root.session.schema.clone();
-                let _ = db_cmd_tx.send(db::DbCmd::LoadTables { schema }).await;
+            if e.kind == db::DbErrorKind::SchemaNotFound {
+                // Let the user pick one that exists instead of guessing.
+                root.status.left = format!(
+                    "Schema '{}' not found. Pick another one.",
+                    root.session.schema
+                );
+                root.session.requests.schemas =
+                    db_cmd_tx.send(Pane::Browse, db::DbCmd::LoadSchemas).await;
             } else {
                 root.status.left = format!("Error: {e}");
+                root.session.last_error = Some(e);
             }
         }
+        db::DbEvt::Ddl { table, sql } => {
+            root.session.ddl = Some(DdlView {
+                table,
+                sql,
+                scroll: 0,
+            });
+            root.status.right = "Enter copies it to the SQL editor, Esc closes.".into();
+        }
+        db::DbEvt::SchemasLoaded { schemas } => {
+            let s = &mut root.session;
+            let current = schemas.iter().position(|x| *x == s.schema);
+            s.schemas = schemas;
+            s.schemas_state.select(Some(current.unwrap_or(0)));
+            s.schema_picker = true;
+            root.status.right = "Enter to switch schema, Esc to stay.".into();
+        }
         db::DbEvt::TablesLoaded { tables } => {
             root.session.tables = tables;
             root.session.tables_state.select(Some(0));
@@ -55,35 +122,188 @@ async fn handle_db(root: &mut RootState, evt: db::DbEvt, db_cmd_tx: &mpsc::Sende
             }
         }
 
+        db::DbEvt::RowCount {
+            schema,
+            table,
+            count,
+        } => {
+            let s = &mut root.session;
+            if count.exact {
+                s.counting = false;
+                root.status.right = format!("{} rows in {table}.", format_count(count.rows));
+            }
+            // A late answer for a table no longer open, or an estimate after
+            // the real count, is dropped.
+            let current = s.schema == schema && s.selected_table.as_deref() == Some(&table);
+            let counted = s.row_count.is_some_and(|c| c.exact);
+            if current && (count.exact || !counted) {
+                s.row_count = Some(count);
+            }
+        }
+        db::DbEvt::CountCancelled => {
+            root.session.counting = false;
+            root.status.right = "Count cancelled.".into();
+        }
+        db::DbEvt::MaterializedViewRefreshed { schema, table } => {
+            let s = &mut root.session;
+            root.status.left = format!("Refreshed materialized view {table}.");
+            // Reload it if it is still on screen, to show the new contents.
+            if s.schema == schema && s.selected_table.as_deref() == Some(&table) {
+                s.reset_page();
+                s.requests.page = db_cmd_tx
+                    .send(
+                        Pane::Browse,
+                        db::DbCmd::LoadTablePage {
+                            schema,
+                            table,
+                            nav: PageNav::First,
+                            cursor: PageCursor::default(),
+                            page_size: s.page_size,
+                        },
+                    )
+                    .await;
+            }
+        }
+
+        db::DbEvt::ParamsDescribed { sql, params } => {
+            let s = &mut root.session;
+            let history = s
+                .param_history
+                .get(&sql)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            s.param_form = Some(ParamForm::new(sql, params, history));
+            root.status.right =
+                "Enter runs, Tab next field, PgUp/PgDn recall values, Esc cancels.".into();
+        }
+
+        db::DbEvt::PlanLoaded { plan } => {
+            let s = &mut root.session;
+            s.sql_running_since = None;
+            root.status.right = match plan.execution_ms {
+                Some(ms) => {
+                    format!("Plan: executed in {ms:.3} ms. Enter folds a node, Esc closes.")
+                }
+                None => "Plan (estimated). Enter folds a node, Esc closes.".into(),
+            };
+            s.plan = Some(PlanView::new(*plan));
+            s.focus = Focus::Results;
+        }
+        db::DbEvt::ExplainCancelled => {
+            root.session.sql_running_since = None;
+            root.status.right = "Explain cancelled.".into();
+        }
+
         db::DbEvt::QueryResult {
             columns,
             rows,
+            order,
+            cursor,
             info,
         } => {
-            root.session.columns = columns;
-            root.session.rows = rows;
-            root.session.results_state.select(Some(0));
+            let s = &mut root.session;
+            s.last_error = None;
+            let result = s.result_mut(pane);
+            result.columns = columns;
+            result.rows = rows;
+            result.state.select(Some(0));
+            s.page_order = Some(order);
+            s.page_cursor = cursor;
             root.status.right = info;
         }
 
-        db::DbEvt::SqlExecuted { info } => {
-            root.session.sql_last_result = Some(info.clone());
-            root.status.right = info;
+        db::DbEvt::RowBatch { columns, rows } => {
+            let s = &mut root.session;
+            if columns.is_some() {
+                s.sql_more_rows = false;
+            }
+            let result = s.result_mut(pane);
+            if let Some(columns) = columns {
+                result.columns = columns;
+                result.rows = rows;
+                result.state.select(Some(0));
+            } else {
+                result.rows.extend(rows);
+            }
+            root.status.right = format!("Fetching… {} rows", result.rows.len());
         }
-        db::DbEvt::SqlMetaLoaded {
-            schema,
-            tables,
-            columns,
+
+        db::DbEvt::StatementDone {
+            index,
+            count,
+            sql,
+            outcome,
+            elapsed,
         } => {
-            root.session.schema = schema; // keep TUI in sync if we ever change schema via meta command
-            root.session.sql_tables = tables.clone();
-            root.session.tables = tables;
-            root.session.tables_state.select(Some(0));
+            let s = &mut root.session;
+            let step = if count > 1 {
+                format!("[{}/{count}] ", index + 1)
+            } else {
+                String::new()
+            };
+            match &outcome {
+                db::StatementOutcome::Rows {
+                    total,
+                    more_available,
+                } => {
+                    s.sql_more_rows = *more_available;
+                    root.status.right = if *more_available {
+                        format!("{step}Query OK. First {total} rows (memory cap reached, more rows available)")
+                    } else {
+                        format!("{step}Query OK. {total} rows")
+                    };
+                }
+                db::StatementOutcome::Command { tag } => {
+                    s.sql_last_result = Some(tag.clone());
+                    root.status.right = format!("{step}{tag}");
+                }
+                db::StatementOutcome::Failed(e) => {
+                    root.status.left = format!("Error: {e}");
+                    s.last_error = Some(e.clone());
+                }
+                db::StatementOutcome::Cancelled { rows } => {
+                    root.status.right = if *rows > 0 {
+                        format!("{step}Query cancelled after {rows} rows")
+                    } else {
+                        format!("{step}Query cancelled")
+                    };
+                }
+            }
+            s.sql_log.push(StatementLog {
+                sql,
+                outcome,
+                elapsed,
+            });
+        }
 
-            root.session.sql_columns.clear();
-            for (t, cols) in columns {
-                root.session.sql_columns.insert(t, cols);
+        db::DbEvt::ScriptDone {
+            executed,
+            count,
+            failed,
+            elapsed,
+        } => {
+            root.session.sql_running_since = None;
+            if count > 1 {
+                let skipped = count - executed;
+                let mut msg = format!(
+                    "Script: {executed}/{count} statements, {failed} failed, {:.1}s",
+                    elapsed.as_secs_f64()
+                );
+                if skipped > 0 {
+                    msg.push_str(&format!(", {skipped} skipped"));
+                }
+                root.status.right = msg;
+            }
+        }
+
+        db::DbEvt::SqlMetaLoaded { catalog } => {
+            // Answer for a schema switched away from before it arrived.
+            if catalog.schema != root.session.schema {
+                return false;
             }
+            root.session.tables = catalog.relations.iter().map(Relation::entry).collect();
+            root.session.tables_state.select(Some(0));
+            root.session.catalog = catalog;
 
             root.status.left = format!("Schema: {}", root.session.schema);
         }
@@ -97,36 +317,63 @@ async fn handle_db(root: &mut RootState, evt: db::DbEvt, db_cmd_tx: &mpsc::Sende
             .map(|x| x.to_string())
         {
             root.session.selected_table = Some(t.clone());
-            root.session.page = 0;
+            root.session.reset_page();
 
             let schema = root.session.schema.clone();
             let page_size = root.session.page_size;
 
-            let _ = db_cmd_tx
-                .send(db::DbCmd::LoadTablePage {
-                    schema,
-                    table: t,
-                    page: 0,
-                    page_size,
-                })
+            root.session.requests.page = db_cmd_tx
+                .send(
+                    Pane::Browse,
+                    db::DbCmd::LoadTablePage {
+                        schema,
+                        table: t,
+                        nav: PageNav::First,
+                        cursor: PageCursor::default(),
+                        page_size,
+                    },
+                )
                 .await;
         }
     }
+
+    false
 }
 
-async fn handle_input(
-    root: &mut RootState,
-    ev: UiEvent,
-    db_cmd_tx: &mpsc::Sender<db::DbCmd>,
-) -> bool {
+async fn handle_input(root: &mut RootState, ev: UiEvent, db_cmd_tx: &db::DbClient) -> bool {
     use UiEvent::*;
     if root.mode == Mode::Manager {
         return handle_manager_input(root, ev, db_cmd_tx).await;
     }
+    if root.session.quit_prompt {
+        handle_quit_prompt(root, ev, db_cmd_tx).await;
+        return false;
+    }
+    if root.session.schema_picker {
+        handle_schema_picker(root, ev, db_cmd_tx).await;
+        return false;
+    }
+    if root.session.param_form.is_some() {
+        handle_param_form(root, ev, db_cmd_tx).await;
+        return false;
+    }
+    if root.session.ddl.is_some() && root.session.tab == Tab::Browse {
+        handle_ddl_view(root, ev);
+        return false;
+    }
+    let on_plan = root.session.tab == Tab::Sql && root.session.focus == Focus::Results;
+    if on_plan && root.session.plan.is_some() && handle_plan_view(root, &ev) {
+        return false;
+    }
     let s = &mut root.session;
 
     match ev {
-        Quit => return true,
+        Quit => {
+            if s.tx_status == db::TxStatus::Idle {
+                return true;
+            }
+            s.quit_prompt = true;
+        }
 
         SwitchTabBrowse => {
             s.tab = Tab::Browse;
@@ -143,7 +390,10 @@ async fn handle_input(
 
         Nav(dir) => match s.focus {
             Focus::Tables => nav_list(&mut s.tables_state, s.tables.len(), dir),
-            Focus::Results => nav_table(&mut s.results_state, s.rows.len(), dir),
+            Focus::Results => {
+                let result = s.result_mut(pane_of(s.tab));
+                nav_table(&mut result.state, result.rows.len(), dir)
+            }
             Focus::SqlEditor => {}
         },
 
@@ -155,17 +405,31 @@ async fn handle_input(
                     .or_else(|| s.selected_table_from_list().map(|x| x.to_string()));
 
                 if let Some(table) = table {
-                    match dir {
-                        PageDir::Next => s.page += 1,
-                        PageDir::Prev => s.page = (s.page - 1).max(0),
+                    if dir == PageDir::Next && s.at_last_page() {
+                        root.status.right = "Already on the last page.".into();
+                        return false;
                     }
-                    let _ = db_cmd_tx
-                        .send(db::DbCmd::LoadTablePage {
-                            schema: s.schema.clone(),
-                            table,
-                            page: s.page,
-                            page_size: s.page_size,
-                        })
+                    if dir == PageDir::Prev && s.page_cursor.pos == PagePos::FromStart(0) {
+                        root.status.right = "Already on the first page.".into();
+                        return false;
+                    }
+                    let nav = match dir {
+                        PageDir::Next => PageNav::Next,
+                        PageDir::Prev => PageNav::Prev,
+                        PageDir::First => PageNav::First,
+                        PageDir::Last => PageNav::Last,
+                    };
+                    s.requests.page = db_cmd_tx
+                        .send(
+                            Pane::Browse,
+                            db::DbCmd::LoadTablePage {
+                                schema: s.schema.clone(),
+                                table,
+                                nav,
+                                cursor: s.page_cursor.clone(),
+                                page_size: s.page_size,
+                            },
+                        )
                         .await;
                 }
             }
@@ -175,14 +439,18 @@ async fn handle_input(
             if s.tab == Tab::Browse {
                 if let Some(table) = s.selected_table_from_list().map(|x| x.to_string()) {
                     s.selected_table = Some(table.clone());
-                    s.page = 0;
-                    let _ = db_cmd_tx
-                        .send(db::DbCmd::LoadTablePage {
-                            schema: s.schema.clone(),
-                            table,
-                            page: 0,
-                            page_size: s.page_size,
-                        })
+                    s.reset_page();
+                    s.requests.page = db_cmd_tx
+                        .send(
+                            Pane::Browse,
+                            db::DbCmd::LoadTablePage {
+                                schema: s.schema.clone(),
+                                table,
+                                nav: PageNav::First,
+                                cursor: PageCursor::default(),
+                                page_size: s.page_size,
+                            },
+                        )
                         .await;
                 }
             }
@@ -198,11 +466,60 @@ async fn handle_input(
             }
         }
 
+        PickSchema => {
+            if s.tab == Tab::Browse {
+                root.status.right = "Loading schemas…".into();
+                s.requests.schemas = db_cmd_tx.send(Pane::Browse, db::DbCmd::LoadSchemas).await;
+            }
+        }
+
+        ShowDdl => {
+            if s.tab == Tab::Browse {
+                if let Some(table) = s.selected_table_from_list().map(str::to_string) {
+                    root.status.right = format!("Loading DDL of {table}…");
+                    s.requests.ddl = db_cmd_tx
+                        .send(
+                            Pane::Browse,
+                            db::DbCmd::LoadDdl {
+                                schema: s.schema.clone(),
+                                table,
+                            },
+                        )
+                        .await;
+                }
+            }
+        }
+
+        RefreshMatView => {
+            if s.tab == Tab::Browse {
+                match s.selected_entry() {
+                    Some(t) if t.kind == RelationKind::MaterializedView => {
+                        let table = t.name.clone();
+                        root.status.left = format!("Refreshing {table}…");
+                        s.requests.refresh = db_cmd_tx
+                            .send(
+                                Pane::Browse,
+                                db::DbCmd::RefreshMaterializedView {
+                                    schema: s.schema.clone(),
+                                    table,
+                                },
+                            )
+                            .await;
+                    }
+                    Some(_) => root.status.right = "Not a materialized view.".into(),
+                    None => {}
+                }
+            }
+        }
+
         Refresh => {
-            let _ = db_cmd_tx
-                .send(db::DbCmd::LoadTables {
-                    schema: s.schema.clone(),
-                })
+            s.requests.tables = db_cmd_tx
+                .send(
+                    Pane::Browse,
+                    db::DbCmd::LoadTables {
+                        schema: s.schema.clone(),
+                    },
+                )
                 .await;
         }
 
@@ -273,12 +590,120 @@ async fn handle_input(
                 let sql = s.sql_text.trim().to_string();
                 if sql.is_empty() {
                     root.status.right = "SQL is empty.".into();
+                } else if s.sql_running_since.is_some() {
+                    root.status.right = "A query is already running (Esc to cancel).".into();
+                } else if !param_names(&sql, s.driver).is_empty() {
+                    // Ask for values first; the form runs it.
+                    root.status.right = "Describing parameters…".into();
+                    s.requests.sql = db_cmd_tx
+                        .send(Pane::Sql, db::DbCmd::DescribeParams { sql })
+                        .await;
                 } else {
-                    let _ = db_cmd_tx.send(db::DbCmd::ExecuteSql { sql }).await;
+                    execute_sql(s, sql, Vec::new(), db_cmd_tx).await;
                 }
             }
         }
 
+        Explain | ExplainAnalyze => {
+            if s.tab == Tab::Sql {
+                let cursor = s.sql_text[..s.sql_cursor].chars().count();
+                let analyze = matches!(ev, ExplainAnalyze);
+                match statement_at(&s.sql_text, cursor, s.driver) {
+                    None => root.status.right = "SQL is empty.".into(),
+                    Some(_) if s.sql_running_since.is_some() => {
+                        root.status.right = "A query is already running (Esc to cancel).".into();
+                    }
+                    Some(stmt) => {
+                        s.sql_running_since = Some(Instant::now());
+                        s.last_error = None;
+                        root.status.right = if analyze {
+                            "Running EXPLAIN ANALYZE… (Esc to cancel)".into()
+                        } else {
+                            "Running EXPLAIN…".into()
+                        };
+                        s.requests.sql = db_cmd_tx
+                            .send(
+                                Pane::Sql,
+                                db::DbCmd::Explain {
+                                    sql: stmt.sql,
+                                    analyze,
+                                    buffers: analyze,
+                                },
+                            )
+                            .await;
+                    }
+                }
+            }
+        }
+
+        CancelQuery => {
+            // Esc stops what runs in the tab on screen, and only that.
+            let running = match s.tab {
+                Tab::Sql => s.sql_running_since.is_some(),
+                Tab::Browse => s.counting,
+            };
+            if running {
+                root.status.right = "Cancelling…".into();
+                db_cmd_tx.send(pane_of(s.tab), db::DbCmd::Cancel).await;
+            } else if s.completion.visible {
+                s.completion.visible = false;
+            } else {
+                s.last_error = None;
+            }
+        }
+
+        ToggleAutocommit => {
+            s.autocommit = !s.autocommit;
+            s.requests.tx = db_cmd_tx
+                .send(Pane::Sql, db::DbCmd::SetAutocommit(s.autocommit))
+                .await;
+            root.status.middle = if s.autocommit {
+                "Autocommit: ON".into()
+            } else {
+                "Autocommit: OFF".into()
+            };
+        }
+        BeginTx => {
+            s.requests.tx = db_cmd_tx.send(Pane::Sql, db::DbCmd::Begin).await;
+        }
+        CommitTx => {
+            s.requests.tx = db_cmd_tx.send(Pane::Sql, db::DbCmd::Commit).await;
+        }
+        RollbackTx => {
+            s.requests.tx = db_cmd_tx.send(Pane::Sql, db::DbCmd::Rollback).await;
+        }
+        CountRows => {
+            if s.tab == Tab::Browse && !s.counting {
+                if let Some(table) = s.selected_table.clone() {
+                    s.counting = true;
+                    root.status.right = "Counting rows… (Esc to cancel)".into();
+                    s.requests.count = db_cmd_tx
+                        .send(
+                            Pane::Browse,
+                            db::DbCmd::CountRows {
+                                schema: s.schema.clone(),
+                                table,
+                            },
+                        )
+                        .await;
+                }
+            }
+        }
+
+        ResetSession => {
+            root.status.right = "Resetting session…".into();
+            s.requests.tx = db_cmd_tx.send(Pane::Sql, db::DbCmd::ResetSession).await;
+        }
+
+        ToggleStopOnError => {
+            s.sql_stop_on_error = !s.sql_stop_on_error;
+            root.status.middle = if s.sql_stop_on_error {
+                "Scripts: stop on error".into()
+            } else {
+                "Scripts: continue on error".into()
+            };
+        }
+
         CycleTheme => {
             root.cycle_theme();
         }
@@ -319,7 +744,207 @@ async fn handle_input(
     false
 }
 
-fn toggle_focus(s: &mut crate::app::state::SessionState) {
+/// Sends a script to run, clearing what the last run left on screen.
+async fn execute_sql(
+    s: &mut SessionState,
+    sql: String,
+    params: Vec<QueryParam>,
+    db_cmd_tx: &db::DbClient,
+) {
+    s.sql_running_since = Some(Instant::now());
+    s.last_error = None;
+    s.sql_log.clear();
+    s.plan = None;
+    s.requests.sql = db_cmd_tx
+        .send(
+            Pane::Sql,
+            db::DbCmd::ExecuteSql {
+                sql,
+                max_bytes: s.result_max_bytes,
+                stop_on_error: s.sql_stop_on_error,
+                params,
+            },
+        )
+        .await;
+}
+
+/// Typing edits the selected value, Backspace on an empty one makes it NULL,
+/// Up/Down/Tab move between fields, PgUp/PgDn recall older or newer values,
+/// Enter runs the script and Esc drops it.
+async fn handle_param_form(root: &mut RootState, ev: UiEvent, db_cmd_tx: &db::DbClient) {
+    let s = &mut root.session;
+    let Some(form) = &mut s.param_form else {
+        return;
+    };
+    let len = form.params.len();
+    match ev {
+        UiEvent::SqlInput(ch) => {
+            if let Some(p) = form.params.get_mut(form.selected) {
+                p.value.get_or_insert_with(String::new).push(ch);
+            }
+        }
+        UiEvent::SqlBackspace => {
+            if let Some(p) = form.params.get_mut(form.selected) {
+                match &mut p.value {
+                    Some(v) if !v.is_empty() => {
+                        v.pop();
+                    }
+                    _ => p.value = None,
+                }
+            }
+        }
+        UiEvent::Nav(NavDir::Up) => form.selected = form.selected.saturating_sub(1),
+        UiEvent::Nav(NavDir::Down) => {
+            form.selected = (form.selected + 1).min(len.saturating_sub(1))
+        }
+        UiEvent::ToggleFocus => form.selected = (form.selected + 1) % len.max(1),
+        UiEvent::Page(dir @ (PageDir::Prev | PageDir::Next)) => {
+            let history = s
+                .param_history
+                .get(&form.sql)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let recall = match dir {
+                PageDir::Prev => form.recall + 1,
+                _ => form.recall.saturating_sub(1),
+            };
+            match history.get(recall) {
+                Some(values) => {
+                    form.recall = recall;
+                    form.fill(values);
+                    root.status.right = format!("Values {} of {}.", recall + 1, history.len());
+                }
+                None => root.status.right = "No more remembered values.".into(),
+            }
+        }
+        UiEvent::CancelQuery => {
+            s.param_form = None;
+            root.status.right = "Cancelled.".into();
+        }
+        UiEvent::OpenSelection | UiEvent::ExecuteSql => {
+            let Some(form) = s.param_form.take() else {
+                return;
+            };
+            s.remember_params(&form.sql, &form.params);
+            if let Err(e) = crate::storage::params::save(&s.param_history) {
+                root.status.right = format!("{e:#}");
+            }
+            execute_sql(s, form.sql, form.params, db_cmd_tx).await;
+        }
+        _ => {}
+    }
+}
+
+/// `c` commits and quits, `r` rolls back and quits, Esc goes back.
+async fn handle_quit_prompt(root: &mut RootState, ev: UiEvent, db_cmd_tx: &db::DbClient) {
+    let s = &mut root.session;
+    let cmd = match ev {
+        UiEvent::SqlInput('c') => db::DbCmd::Commit,
+        UiEvent::SqlInput('r') => db::DbCmd::Rollback,
+        UiEvent::CancelQuery => {
+            s.quit_prompt = false;
+            return;
+        }
+        _ => return,
+    };
+    s.quit_prompt = false;
+    s.quit_after_tx = true;
+    s.requests.tx = db_cmd_tx.send(Pane::Sql, cmd).await;
+}
+
+/// Up/Down choose, Enter switches, Esc keeps the current schema.
+async fn handle_schema_picker(root: &mut RootState, ev: UiEvent, db_cmd_tx: &db::DbClient) {
+    let s = &mut root.session;
+    match ev {
+        UiEvent::Nav(dir) => nav_list(&mut s.schemas_state, s.schemas.len(), dir),
+        UiEvent::CancelQuery => s.schema_picker = false,
+        UiEvent::OpenSelection => {
+            s.schema_picker = false;
+            let picked = s.schemas_state.selected().and_then(|i| s.schemas.get(i));
+            let Some(schema) = picked.cloned() else {
+                return;
+            };
+            // Reloading the current schema is what a failed one needs too.
+            s.switch_schema(schema);
+            root.status.left = format!("Loading schema {}…", s.schema);
+            s.requests.tables = db_cmd_tx
+                .send(
+                    Pane::Browse,
+                    db::DbCmd::LoadSqlMeta {
+                        schema: s.schema.clone(),
+                    },
+                )
+                .await;
+        }
+        _ => {}
+    }
+}
+
+/// Keys on the plan tree: Up/Down select, Enter folds or unfolds,
+/// Left/Right fold and unfold, Esc closes the plan. Returns false for
+/// anything it leaves to the normal handling.
+fn handle_plan_view(root: &mut RootState, ev: &UiEvent) -> bool {
+    let s = &mut root.session;
+    let Some(plan) = &mut s.plan else {
+        return false;
+    };
+    let len = plan.rows().len();
+    match ev {
+        UiEvent::Nav(dir) => nav_list(&mut plan.state, len, *dir),
+        UiEvent::Page(PageDir::First) => plan.state.select(Some(0)),
+        UiEvent::Page(PageDir::Last) => plan.state.select(Some(len.saturating_sub(1))),
+        UiEvent::OpenSelection => plan.toggle_selected(),
+        UiEvent::SqlMoveCursorLeft => plan.set_collapsed(true),
+        UiEvent::SqlMoveCursorRight => plan.set_collapsed(false),
+        // Esc stops a running query first.
+        UiEvent::CancelQuery if s.sql_running_since.is_none() => {
+            s.plan = None;
+            s.focus = Focus::SqlEditor;
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Up/Down and PgUp/PgDn scroll, Enter copies the DDL into the SQL editor,
+/// Esc closes it.
+fn handle_ddl_view(root: &mut RootState, ev: UiEvent) {
+    let s = &mut root.session;
+    let Some(ddl) = &mut s.ddl else {
+        return;
+    };
+    let last = ddl.sql.lines().count().saturating_sub(1) as u16;
+    match ev {
+        UiEvent::Nav(NavDir::Up) => ddl.scroll = ddl.scroll.saturating_sub(1),
+        UiEvent::Nav(NavDir::Down) => ddl.scroll = (ddl.scroll + 1).min(last),
+        UiEvent::Page(PageDir::Prev) => ddl.scroll = ddl.scroll.saturating_sub(10),
+        UiEvent::Page(PageDir::Next) => ddl.scroll = (ddl.scroll + 10).min(last),
+        UiEvent::Page(PageDir::First) => ddl.scroll = 0,
+        UiEvent::Page(PageDir::Last) => ddl.scroll = last,
+        UiEvent::CancelQuery => s.ddl = None,
+        UiEvent::OpenSelection => {
+            let Some(ddl) = s.ddl.take() else {
+                return;
+            };
+            s.sql_cursor = ddl.sql.len();
+            s.sql_text = ddl.sql;
+            s.tab = Tab::Sql;
+            s.focus = Focus::SqlEditor;
+            root.status.right = format!("DDL of {} is in the SQL editor.", ddl.table);
+        }
+        _ => {}
+    }
+}
+
+/// The pane a command sent from `tab` feeds.
+fn pane_of(tab: Tab) -> Pane {
+    match tab {
+        Tab::Browse => Pane::Browse,
+        Tab::Sql => Pane::Sql,
+    }
+}
+
+fn toggle_focus(s: &mut SessionState) {
     use Focus::*;
     use Tab::*;
 
@@ -358,18 +983,7 @@ fn nav_table(state: &mut ratatui::widgets::TableState, len: usize, dir: NavDir)
     state.select(Some(ni));
 }
 
-fn looks_like_missing_schema(err: &str) -> bool {
-    let e = err.to_ascii_lowercase();
-    // custom check for our explicit error
-    // should be handled better in the future, this is only for MVP
-    e.contains("schema") && e.contains("does not exist")
-}
-
-async fn handle_manager_input(
-    root: &mut RootState,
-    ev: UiEvent,
-    db_cmd_tx: &mpsc::Sender<db::DbCmd>,
-) -> bool {
+async fn handle_manager_input(root: &mut RootState, ev: UiEvent, db_cmd_tx: &db::DbClient) -> bool {
     use UiEvent::*;
 
     match ev {
@@ -382,19 +996,31 @@ async fn handle_manager_input(
 
         OpenSelection => {
             if let Some(p) = root.manager.selected().cloned() {
+                root.session.driver = p.driver;
                 root.session.schema = p.schema.clone();
+                root.session.page_size = p.page_size;
+                // A query still running on the old connection goes with it.
+                root.session.sql_running_since = None;
                 root.status.left = format!("Connecting to {}", p.name);
 
-                let _ = db_cmd_tx
-                    .send(db::DbCmd::Connect {
-                        database_url: p.database_url,
-                    })
+                root.session.requests.connect = db_cmd_tx
+                    .send(
+                        Pane::Browse,
+                        db::DbCmd::Connect {
+                            driver: p.driver,
+                            database_url: p.database_url,
+                            options: p.options,
+                        },
+                    )
                     .await;
 
-                let _ = db_cmd_tx
-                    .send(db::DbCmd::LoadSqlMeta {
-                        schema: root.session.schema.clone(),
-                    })
+                root.session.requests.tables = db_cmd_tx
+                    .send(
+                        Pane::Browse,
+                        db::DbCmd::LoadSqlMeta {
+                            schema: root.session.schema.clone(),
+                        },
+                    )
                     .await;
 
                 root.mode = Mode::Viewer;
@@ -407,7 +1033,9 @@ async fn handle_manager_input(
 
         SwitchTabBrowse | SwitchTabSql | ToggleFocus | Page(_) | ExecuteSql | SqlInput(_)
         | SqlBackspace | SqlNewline | SqlMoveCursorLeft | SqlMoveCursorRight | ToggleCompletion
-        | CompletionNext | CompletionPrev | AcceptCompletion | Refresh => {
+        | CompletionNext | CompletionPrev | AcceptCompletion | Refresh | CancelQuery
+        | ToggleStopOnError | ToggleAutocommit | BeginTx | CommitTx | RollbackTx | ResetSession
+        | CountRows | PickSchema | RefreshMatView | ShowDdl | Explain | ExplainAnalyze => {
             // ignore in manager for now
         }
     }
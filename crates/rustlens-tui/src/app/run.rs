@@ -3,6 +3,7 @@ use tokio::sync::mpsc;
 
 use crate::{config::AppConfig, LaunchMode};
 use rustlens_core::db;
+use rustlens_core::model::connection::Driver;
 
 pub fn run_app(cfg: AppConfig, mode: LaunchMode) -> Result<()> {
     // TODO: review possible enhancements on tui run
@@ -13,27 +14,46 @@ pub fn run_app(cfg: AppConfig, mode: LaunchMode) -> Result<()> {
     rt.block_on(async move {
         let mut terminal = crate::term::terminal::TerminalGuard::new()?;
 
-        let (db_cmd_tx, db_cmd_rx) = mpsc::channel::<db::DbCmd>(64);
-        let (db_evt_tx, mut db_evt_rx) = mpsc::channel::<db::DbEvt>(256);
+        let (db_req_tx, db_cmd_rx) = mpsc::channel::<db::DbRequest>(64);
+        let (db_evt_tx, mut db_evt_rx) = mpsc::channel::<db::DbReply>(256);
+        let db_cmd_tx = db::DbClient::new(db_req_tx);
 
         tokio::spawn(async move {
             if let Err(e) = db::worker::run(db_cmd_rx, db_evt_tx).await {
                 eprintln!("db worker crashed: {e:#}");
             }
         });
+        let viewer = matches!(mode, LaunchMode::Viewer { .. });
         let mut root = crate::app::state::RootState::new(cfg.clone(), mode);
+        match crate::storage::params::load() {
+            Ok(history) => root.session.param_history = history,
+            Err(e) => root.status.right = format!("{e:#}"),
+        }
 
-        // In viewer mode, start immediately by loading tables
-        db_cmd_tx
-            .send(db::DbCmd::LoadSqlMeta {
-                schema: root.session.schema.clone(),
-            })
-            .await
-            .ok();
+        // In viewer mode, start immediately by connecting and loading tables
+        if viewer {
+            if let Some(driver) = Driver::from_url(&cfg.database_url) {
+                let database_url = cfg.database_url.clone();
+                root.session.requests.connect = db_cmd_tx
+                    .send(
+                        db::Pane::Browse,
+                        db::DbCmd::Connect {
+                            driver,
+                            database_url,
+                            options: cfg.connection.clone(),
+                        },
+                    )
+                    .await;
+            }
+            let schema = root.session.schema.clone();
+            root.session.requests.tables = db_cmd_tx
+                .send(db::Pane::Browse, db::DbCmd::LoadSqlMeta { schema })
+                .await;
+        }
 
         use crate::app::event::AppEvent;
 
-        loop {
+        'ui: loop {
             if let Some(input) = crate::term::input::poll_next_event(root.session.tick_rate)? {
                 if crate::app::reducer::reduce(&mut root, AppEvent::Input(input), &db_cmd_tx).await
                 {
@@ -42,8 +62,10 @@ pub fn run_app(cfg: AppConfig, mode: LaunchMode) -> Result<()> {
             }
 
             while let Ok(evt) = db_evt_rx.try_recv() {
-                if crate::app::reducer::reduce(&mut root, AppEvent::Db(evt), &db_cmd_tx).await {
-                    break;
+                if crate::app::reducer::reduce(&mut root, AppEvent::Db(Box::new(evt)), &db_cmd_tx)
+                    .await
+                {
+                    break 'ui;
                 }
             }
 
@@ -74,8 +74,9 @@ pub fn refresh_completion(s: &mut SessionState) {
 
     // Column completion: "... table_alias_or_table.<prefix>"
     if let Some(table_like) = dot {
-        if let Some(cols) = s.sql_columns.get(table_like) {
-            push_prefix(items.as_mut(), cols.iter().map(String::as_str), prefix, 30);
+        if let Some(rel) = s.catalog.relation(table_like) {
+            let cols = rel.columns.iter().map(|c| c.name.as_str());
+            push_prefix(items.as_mut(), cols, prefix, 30);
             finalize(s, start, items);
             return;
         }
@@ -84,7 +85,7 @@ pub fn refresh_completion(s: &mut SessionState) {
     if matches!(ctx.as_deref(), Some("FROM" | "JOIN" | "INTO" | "UPDATE")) {
         push_prefix(
             items.as_mut(),
-            s.sql_tables.iter().map(String::as_str),
+            s.catalog.relations.iter().map(|r| r.name.as_str()),
             prefix,
             30,
         );
@@ -95,7 +96,7 @@ pub fn refresh_completion(s: &mut SessionState) {
     push_prefix(items.as_mut(), KEYWORDS.iter().copied(), prefix, 30);
     push_prefix(
         items.as_mut(),
-        s.sql_tables.iter().map(String::as_str),
+        s.catalog.relations.iter().map(|r| r.name.as_str()),
         prefix,
         30,
     );
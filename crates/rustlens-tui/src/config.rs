@@ -1,25 +1,57 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use rustlens_core::model::connection::{ConnectionOptions, ConnectionProfile, Driver};
 use serde::Deserialize;
 use std::fs;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct AppConfig {
+    /// May be left out when `[[profiles]]` name the connections.
+    #[serde(default)]
     pub database_url: String,
-    #[serde(default = "default_schema")]
-    pub schema: String,
+    /// Left out, the driver's default for `database_url` applies.
+    #[serde(default)]
+    pub schema: Option<String>,
     #[serde(default = "default_page_size")]
     pub page_size: i64,
+    /// Stop fetching SQL results once they take roughly this many MiB.
+    #[serde(default = "default_result_memory_mb")]
+    pub result_memory_mb: usize,
+    /// Skip the rest of a multi-statement script after a failure.
+    #[serde(default = "default_stop_on_error")]
+    pub stop_on_error: bool,
+    /// TLS, timeouts, pool size and `application_name`, as top-level keys.
+    #[serde(flatten)]
+    pub connection: ConnectionOptions,
+    /// The connections Manager mode offers. Once loaded, the top-level
+    /// `database_url` with its options comes first, as `default`.
+    #[serde(default)]
+    pub profiles: Vec<ConnectionProfile>,
 }
 
-fn default_schema() -> String {
-    "public".to_string()
-}
 fn default_page_size() -> i64 {
     200
 }
+pub fn default_result_memory_mb() -> usize {
+    64
+}
+pub fn default_stop_on_error() -> bool {
+    true
+}
 
 pub fn load_from_file(path: &str) -> Result<AppConfig> {
     let s = fs::read_to_string(path).with_context(|| format!("Could not read {}", path))?;
-    let cfg: AppConfig = toml::from_str(&s).context("Invalid config.toml")?;
+    let mut cfg: AppConfig = toml::from_str(&s).context("Invalid config.toml")?;
+    if !cfg.database_url.is_empty() {
+        let Some(driver) = Driver::from_url(&cfg.database_url) else {
+            bail!("Unsupported database_url in {path}");
+        };
+        let mut top = ConnectionProfile::new("default", driver, cfg.database_url.clone());
+        top.schema = cfg.schema.clone();
+        top.options = cfg.connection.clone();
+        cfg.profiles.insert(0, top);
+    }
+    if cfg.profiles.is_empty() {
+        bail!("{path} names no database: set database_url or add [[profiles]]");
+    }
     Ok(cfg)
 }
@@ -1,16 +1,20 @@
 mod config;
+mod storage;
 
 pub mod app;
 pub mod term;
 pub mod ui;
 
 use anyhow::Result;
+use rustlens_core::model::connection::ConnectionOptions;
 
 #[derive(Debug, Clone)]
 pub enum LaunchMode {
     Viewer {
         database_url: String,
         schema: Option<String>,
+        /// TLS, timeouts and pool size, as given on the command line.
+        options: Box<ConnectionOptions>,
     },
     Manager,
 }
@@ -23,12 +27,17 @@ pub fn run(mode: LaunchMode) -> Result<()> {
         LaunchMode::Viewer {
             database_url,
             schema,
+            options,
         } => {
             // In direct viewer mode we don't require config.toml.
             config::AppConfig {
                 database_url: database_url.clone(),
-                schema: schema.clone().unwrap_or_else(|| "public".to_string()),
+                schema: schema.clone(),
                 page_size: 200,
+                result_memory_mb: config::default_result_memory_mb(),
+                stop_on_error: config::default_stop_on_error(),
+                connection: (**options).clone(),
+                profiles: Vec::new(),
             }
         }
 
@@ -0,0 +1,25 @@
+use anyhow::{Context, Result};
+use rustlens_core::model::value::QueryParam;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The values each parameterized query ran with, newest first.
+pub type ParamHistory = HashMap<String, Vec<Vec<QueryParam>>>;
+
+/// Kept next to config.toml, in the directory the app starts from.
+const PATH: &str = "param-history.json";
+
+/// The remembered values; none before the first parameterized run.
+pub fn load() -> Result<ParamHistory> {
+    if !Path::new(PATH).exists() {
+        return Ok(ParamHistory::new());
+    }
+    let s = fs::read_to_string(PATH).with_context(|| format!("Could not read {PATH}"))?;
+    serde_json::from_str(&s).with_context(|| format!("Invalid {PATH}"))
+}
+
+pub fn save(history: &ParamHistory) -> Result<()> {
+    let s = serde_json::to_string_pretty(history)?;
+    fs::write(PATH, s).with_context(|| format!("Could not write {PATH}"))
+}
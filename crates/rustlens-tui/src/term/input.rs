@@ -17,6 +17,9 @@ pub enum UiEvent {
     Page(PageDir),
     OpenSelection,
     Refresh,
+    PickSchema,
+    RefreshMatView,
+    ShowDdl,
 
     // SQL editor input
     SqlInput(char),
@@ -25,6 +28,16 @@ pub enum UiEvent {
     SqlMoveCursorLeft,
     SqlMoveCursorRight,
     ExecuteSql,
+    Explain,
+    ExplainAnalyze,
+    CancelQuery,
+    ToggleStopOnError,
+    ToggleAutocommit,
+    BeginTx,
+    CommitTx,
+    RollbackTx,
+    ResetSession,
+    CountRows,
     ToggleCompletion,
     CompletionNext,
     CompletionPrev,
@@ -49,12 +62,27 @@ pub fn poll_next_event(tick: Duration) -> Result<Option<UiEvent>> {
 
                 (KeyCode::PageUp, _) => UiEvent::Page(PageDir::Prev),
                 (KeyCode::PageDown, _) => UiEvent::Page(PageDir::Next),
+                (KeyCode::Home, _) => UiEvent::Page(PageDir::First),
+                (KeyCode::End, _) => UiEvent::Page(PageDir::Last),
+                (KeyCode::F(4), _) => UiEvent::CountRows,
 
                 (KeyCode::F(5), KeyModifiers::CONTROL) => UiEvent::ExecuteSql,
                 (KeyCode::Enter, KeyModifiers::CONTROL) => UiEvent::ExecuteSql,
+                (KeyCode::F(5), _) => UiEvent::RefreshMatView,
                 (KeyCode::Enter, KeyModifiers::NONE) => UiEvent::OpenSelection,
+                (KeyCode::Esc, _) => UiEvent::CancelQuery,
 
                 (KeyCode::Char('r'), KeyModifiers::CONTROL) => UiEvent::Refresh,
+                (KeyCode::Char('p'), KeyModifiers::CONTROL) => UiEvent::PickSchema,
+                (KeyCode::Char('d'), KeyModifiers::CONTROL) => UiEvent::ShowDdl,
+                (KeyCode::Char('x'), KeyModifiers::CONTROL) => UiEvent::Explain,
+                (KeyCode::Char('x'), KeyModifiers::ALT) => UiEvent::ExplainAnalyze,
+                (KeyCode::Char('e'), KeyModifiers::CONTROL) => UiEvent::ToggleStopOnError,
+                (KeyCode::Char('a'), KeyModifiers::CONTROL) => UiEvent::ToggleAutocommit,
+                (KeyCode::F(6), _) => UiEvent::BeginTx,
+                (KeyCode::F(7), _) => UiEvent::CommitTx,
+                (KeyCode::F(8), _) => UiEvent::RollbackTx,
+                (KeyCode::F(9), _) => UiEvent::ResetSession,
 
                 // SQL editing primitives
                 (KeyCode::Backspace, _) => UiEvent::SqlBackspace,
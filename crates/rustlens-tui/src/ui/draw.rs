@@ -1,7 +1,9 @@
 use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::widgets::Clear;
 use ratatui::Frame;
+use rustlens_core::db::TxStatus;
 
-use crate::app::state::{Mode, RootState};
+use crate::app::state::{Mode, RootState, Tab};
 use crate::ui::widgets::BottomBarMode;
 use crate::ui::{layout, widgets};
 
@@ -27,15 +29,48 @@ pub fn draw(f: &mut Frame, root: &mut RootState) {
         Mode::Manager => crate::ui::screens::manager::draw(f, root, rects.main, &theme),
     }
 
+    // Redrawn every tick, so the elapsed time keeps counting.
+    // Browse stays usable meanwhile; Esc only cancels from the SQL tab.
+    let middle = match (root.session.sql_running_since, root.session.tab) {
+        (Some(since), Tab::Sql) => format!(
+            "running… {:.1}s (Esc to cancel)",
+            since.elapsed().as_secs_f64()
+        ),
+        (Some(since), Tab::Browse) => {
+            format!("SQL running… {:.1}s", since.elapsed().as_secs_f64())
+        }
+        (None, _) => root.status.middle.clone(),
+    };
+
+    let tx = match (root.session.tx_status, root.session.autocommit) {
+        (TxStatus::Idle, true) => "",
+        (TxStatus::Idle, false) => "[autocommit off] ",
+        (TxStatus::Active, _) => "[in transaction] ",
+        (TxStatus::Failed, _) => "[transaction failed] ",
+    };
+    let left = format!("{tx}{}", root.status.left);
+
     f.render_widget(
         widgets::bottom_bar(
             rects.bottom.width,
-            &root.status.left,
-            &root.status.middle,
+            &left,
+            &middle,
             &root.status.right,
             BottomBarMode::MiddleAndRightRightAligned,
             &theme,
         ),
         rects.bottom,
     );
+
+    if let Some(form) = &root.session.param_form {
+        let (panel, w, h) = widgets::param_form(form, &theme);
+        f.render_widget(Clear, widgets::centered(rects.main, w, h));
+        f.render_widget(panel, widgets::centered(rects.main, w, h));
+    }
+
+    if root.session.quit_prompt {
+        let (prompt, w, h) = widgets::quit_prompt(root.session.tx_status, &theme);
+        f.render_widget(Clear, widgets::centered(rects.main, w, h));
+        f.render_widget(prompt, widgets::centered(rects.main, w, h));
+    }
 }
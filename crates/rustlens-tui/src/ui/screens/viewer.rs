@@ -12,16 +12,49 @@ pub fn draw(f: &mut Frame, root: &mut RootState, area: Rect, theme: &Theme) {
         crate::app::state::Tab::Browse => {
             let [left, right] = widgets::split_main(area);
 
-            let list = widgets::tables_list(&s.tables, s.focus, theme);
+            let list = widgets::tables_list(&s.tables, &s.schema, s.focus, theme);
             f.render_stateful_widget(list, left, &mut s.tables_state);
 
             let title = match &s.selected_table {
-                Some(t) => format!("Results: {} | page {}", t, s.page + 1),
+                Some(t) => format!("Results: {} | {}", t, s.page_label()),
                 None => "Results".to_string(),
             };
 
-            let table = widgets::results_table(&s.columns, &s.rows, s.focus, title, theme);
-            f.render_stateful_widget(table, right, &mut s.results_state);
+            let right = match &s.last_error {
+                Some(err) => {
+                    let (panel, h) = widgets::error_panel(err, None, theme);
+                    let [panel_area, rest] = split_top(right, h);
+                    f.render_widget(panel, panel_area);
+                    rest
+                }
+                None => right,
+            };
+
+            match &s.ddl {
+                Some(ddl) => {
+                    let highlighted = root.sql_syntax.highlight(&ddl.sql);
+                    let view = widgets::ddl_view(highlighted, &ddl.table, ddl.scroll, theme);
+                    f.render_widget(view, right);
+                }
+                None => {
+                    let result = &mut s.browse_result;
+                    let table = widgets::results_table(
+                        &result.columns,
+                        &result.rows,
+                        s.focus,
+                        title,
+                        theme,
+                    );
+                    f.render_stateful_widget(table, right, &mut result.state);
+                }
+            }
+
+            if s.schema_picker {
+                let (picker, w, h) = widgets::schema_picker(&s.schemas, theme);
+                let popup = widgets::centered(area, w, h);
+                f.render_widget(ratatui::widgets::Clear, popup);
+                f.render_stateful_widget(picker, popup, &mut s.schemas_state);
+            }
         }
 
         crate::app::state::Tab::Sql => {
@@ -36,14 +69,59 @@ pub fn draw(f: &mut Frame, root: &mut RootState, area: Rect, theme: &Theme) {
             let highlighted = root.sql_syntax.highlight(&s.sql_text);
             f.render_widget(widgets::sql_editor(highlighted, s.focus, theme), chunks[0]);
 
-            let title = match &s.selected_table {
-                Some(t) => format!("Results: {} | page {}", t, s.page + 1),
-                None => "Results".to_string(),
-            };
+            let mut title = "Results".to_string();
+            if let Some(since) = s.sql_running_since {
+                title.push_str(&format!(
+                    " | running… {:.1}s",
+                    since.elapsed().as_secs_f64()
+                ));
+            } else if s.sql_more_rows {
+                title.push_str(&format!(
+                    " | {} rows, more available",
+                    s.sql_result.rows.len()
+                ));
+            }
 
             let (line, col) = crate::app::sql::cursor::cursor_line_col(&s.sql_text, s.sql_cursor);
-            let table = widgets::results_table(&s.columns, &s.rows, s.focus, title, theme);
-            f.render_stateful_widget(table, chunks[1], &mut s.results_state);
+            // A single statement has nothing to log beyond the status bar.
+            let results = if s.sql_log.len() > 1 {
+                let (log, h) = widgets::script_log(&s.sql_log, 6, theme);
+                let [log_area, rest] = split_top(chunks[1], h);
+                f.render_widget(log, log_area);
+                rest
+            } else {
+                chunks[1]
+            };
+
+            let results = match &s.last_error {
+                Some(err) => {
+                    // ExecuteSql sends the trimmed text; the server counts offsets from there.
+                    let sql = s.sql_text.trim_start();
+                    let (panel, h) = widgets::error_panel(err, Some(sql), theme);
+                    let [panel_area, rest] = split_top(results, h);
+                    f.render_widget(panel, panel_area);
+                    rest
+                }
+                None => results,
+            };
+
+            match &mut s.plan {
+                Some(plan) => {
+                    let tree = widgets::plan_tree(plan, s.focus, theme);
+                    f.render_stateful_widget(tree, results, &mut plan.state);
+                }
+                None => {
+                    let result = &mut s.sql_result;
+                    let table = widgets::results_table(
+                        &result.columns,
+                        &result.rows,
+                        s.focus,
+                        title,
+                        theme,
+                    );
+                    f.render_stateful_widget(table, results, &mut result.state);
+                }
+            }
 
             if s.completion_enabled && s.completion.visible && !s.completion.items.is_empty() {
                 let visible_items = s.completion.items.len().min(8);
@@ -80,6 +158,18 @@ pub fn draw(f: &mut Frame, root: &mut RootState, area: Rect, theme: &Theme) {
         }
     }
 
+    /// A panel of `height` on top (at most half the area), the rest below it.
+    fn split_top(area: Rect, height: u16) -> [Rect; 2] {
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .constraints([
+                ratatui::layout::Constraint::Length(height.min(area.height / 2)),
+                ratatui::layout::Constraint::Min(0),
+            ])
+            .split(area);
+        [chunks[0], chunks[1]]
+    }
+
     fn clamp_u16(v: i32) -> u16 {
         if v < 0 {
             0
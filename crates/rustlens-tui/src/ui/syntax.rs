@@ -18,6 +18,12 @@ impl fmt::Debug for SqlSyntax {
     }
 }
 
+impl Default for SqlSyntax {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SqlSyntax {
     pub fn new() -> Self {
         let mut hl = Highlighter::new(themes::one_dark());
@@ -32,6 +32,8 @@ pub struct Theme {
     pub status_left: Style,
     pub status_right: Style,
     pub status_middle: Style,
+
+    pub error: Style,
 }
 
 impl Theme {
@@ -66,6 +68,8 @@ impl Theme {
                 status_left: Style::default().fg(Color::White),
                 status_right: Style::default().bg(Color::Cyan).fg(Color::Black),
                 status_middle: Style::default().fg(Color::Gray),
+
+                error: Style::default().fg(Color::LightRed),
             },
 
             ThemeKind::SolarizedDark => {
@@ -99,6 +103,8 @@ impl Theme {
                     status_left: Style::default().fg(Color::Yellow),
                     status_right: Style::default().bg(Color::Yellow).fg(Color::Black),
                     status_middle: Style::default().fg(Color::DarkGray),
+
+                    error: Style::default().fg(Color::Red),
                 }
             }
 
@@ -131,6 +137,8 @@ impl Theme {
                 status_left: Style::default().fg(Color::LightRed),
                 status_right: Style::default().bg(Color::LightRed).fg(Color::Black),
                 status_middle: Style::default().fg(Color::Gray),
+
+                error: Style::default().fg(Color::LightRed),
             },
         }
     }
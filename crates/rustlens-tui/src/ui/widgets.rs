@@ -1,11 +1,17 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::Modifier,
     text::{Line, Span, Text},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Row, Table, Tabs},
+    widgets::{Block, Borders, Cell, List, ListItem, Paragraph, Row, Table, Tabs},
 };
-
-use crate::app::state::{Focus, Tab};
+use rustlens_core::db::{DbError, StatementOutcome, TxStatus};
+use rustlens_core::model::catalog::{RelationKind, TableEntry};
+use rustlens_core::model::plan::PlanNode;
+use rustlens_core::model::value::{CellValue, ColumnMeta};
+use rustlens_core::util::value_fmt::cell_to_string;
+
+use crate::app::sql::cursor::cursor_line_col;
+use crate::app::state::{Focus, ParamForm, PlanView, StatementLog, Tab};
 use crate::ui::theme::Theme;
 
 pub enum BottomBarMode {
@@ -66,7 +72,6 @@ pub fn bottom_bar(
         BottomBarMode::MiddleAndRightRightAligned => {
             spans.push(Span::styled(left.to_string(), theme.status_left));
 
-            let w = w;
             let left_len = left.chars().count();
 
             if left_len >= w {
@@ -175,14 +180,25 @@ fn title_pill(title: &str, focused: bool, theme: &Theme) -> Line<'static> {
     Line::from(vec![Span::styled(format!(" {title} "), style)])
 }
 
-pub fn tables_list<'a>(tables: &'a [String], focus: Focus, theme: &Theme) -> List<'a> {
+pub fn tables_list<'a>(
+    tables: &'a [TableEntry],
+    schema: &str,
+    focus: Focus,
+    theme: &Theme,
+) -> List<'a> {
     let items: Vec<ListItem> = tables
         .iter()
-        .map(|t| ListItem::new(Line::from(Span::styled(t.as_str(), theme.list_item))))
+        .map(|t| {
+            let mut spans = vec![Span::styled(t.name.as_str(), theme.list_item)];
+            if let Some(kind) = kind_suffix(t.kind) {
+                spans.push(Span::styled(kind, theme.muted));
+            }
+            ListItem::new(Line::from(spans))
+        })
         .collect();
 
     let focused = matches!(focus, Focus::Tables);
-    let title = title_pill("Tables", focused, theme);
+    let title = title_pill(&format!("Tables: {schema}"), focused, theme);
 
     List::new(items)
         .block(block_with_border(title, focused, theme))
@@ -191,9 +207,20 @@ pub fn tables_list<'a>(tables: &'a [String], focus: Focus, theme: &Theme) -> Lis
         .highlight_symbol("▶ ")
 }
 
+/// Marks everything in the table list that is not a plain table.
+fn kind_suffix(kind: RelationKind) -> Option<&'static str> {
+    match kind {
+        RelationKind::Table => None,
+        RelationKind::View => Some(" (view)"),
+        RelationKind::MaterializedView => Some(" (matview)"),
+        RelationKind::PartitionedTable => Some(" (partitioned)"),
+        RelationKind::ForeignTable => Some(" (foreign)"),
+    }
+}
+
 pub fn results_table<'a>(
-    columns: &'a [String],
-    rows: &'a [Vec<String>],
+    columns: &'a [ColumnMeta],
+    rows: &'a [Vec<CellValue>],
     focus: Focus,
     title: String,
     theme: &Theme,
@@ -201,11 +228,36 @@ pub fn results_table<'a>(
     let focused = matches!(focus, Focus::Results);
     let title = title_pill(&title, focused, theme);
 
-    let header = Row::new(columns.iter().cloned()).style(theme.table_header);
+    let header = Row::new(columns.iter().map(|c| {
+        let line = Line::from(c.name.clone());
+        if c.kind.is_numeric() {
+            Cell::from(line.alignment(Alignment::Right))
+        } else {
+            Cell::from(line)
+        }
+    }))
+    .style(theme.table_header);
 
     let body: Vec<Row> = rows
         .iter()
-        .map(|r| Row::new(r.iter().cloned()).style(theme.table_row))
+        .map(|r| {
+            let cells = r.iter().enumerate().map(|(i, v)| {
+                // NULL gets its own style so it can't be confused with the text 'NULL'.
+                let mut line = if v.is_null() {
+                    Line::from(Span::styled(
+                        "NULL",
+                        theme.muted.add_modifier(Modifier::ITALIC),
+                    ))
+                } else {
+                    Line::from(cell_to_string(v))
+                };
+                if columns.get(i).is_some_and(|c| c.kind.is_numeric()) {
+                    line = line.alignment(Alignment::Right);
+                }
+                Cell::from(line)
+            });
+            Row::new(cells).style(theme.table_row)
+        })
         .collect();
 
     let widths = if columns.is_empty() {
@@ -234,3 +286,306 @@ pub fn sql_editor(
 
     Paragraph::new(Text::from(highlighted.to_vec())).block(block_with_border(title, focused, theme))
 }
+
+/// Read-only DDL in place of the results, scrolled to `scroll`.
+pub fn ddl_view(
+    highlighted: &[Line<'static>],
+    table: &str,
+    scroll: u16,
+    theme: &Theme,
+) -> Paragraph<'static> {
+    let title = title_pill(&format!("DDL: {table}"), true, theme);
+
+    Paragraph::new(Text::from(highlighted.to_vec()))
+        .block(block_with_border(title, true, theme))
+        .scroll((scroll, 0))
+}
+
+/// The EXPLAIN plan as a tree, one node per line with its cost, estimated
+/// and actual rows, time and buffers. Nodes that account for a large share
+/// of the time (or of the cost, without ANALYZE) by themselves stand out.
+pub fn plan_tree(view: &PlanView, focus: Focus, theme: &Theme) -> List<'static> {
+    let plan = &view.plan;
+    let items: Vec<ListItem> = view
+        .rows()
+        .iter()
+        .map(|row| {
+            let node = row.node;
+            let marker = match (node.children.is_empty(), view.collapsed.contains(&row.id)) {
+                (true, _) => "  ",
+                (false, true) => "▸ ",
+                (false, false) => "▾ ",
+            };
+            let share = node.share_of(plan);
+            let name_style = if share >= 0.5 {
+                theme.error.add_modifier(Modifier::BOLD)
+            } else if share >= 0.2 {
+                theme.error
+            } else {
+                theme.list_item
+            };
+
+            let mut spans = vec![
+                Span::raw(format!("{}{marker}", "  ".repeat(row.depth))),
+                Span::styled(node.node_type.clone(), name_style),
+            ];
+            if let Some(detail) = &node.detail {
+                spans.push(Span::styled(format!(" {detail}"), theme.text));
+            }
+            spans.push(Span::styled(plan_figures(node, share), theme.muted));
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let focused = matches!(focus, Focus::Results);
+    let mut title = String::from(if plan.analyzed {
+        "Plan"
+    } else {
+        "Plan (estimated)"
+    });
+    if let Some(ms) = plan.planning_ms {
+        title.push_str(&format!(" | planning {ms:.3} ms"));
+    }
+    if let Some(ms) = plan.execution_ms {
+        title.push_str(&format!(" | execution {ms:.3} ms"));
+    }
+    let title = title_pill(&title, focused, theme);
+
+    List::new(items)
+        .block(block_with_border(title, focused, theme))
+        .style(theme.text)
+        .highlight_style(theme.list_item_selected)
+}
+
+/// `cost=… rows=… actual=… loops=… time=… (share) hit=… read=…` for one node.
+fn plan_figures(node: &PlanNode, share: f64) -> String {
+    let mut out = format!(
+        "  cost={:.2}..{:.2} rows={}",
+        node.startup_cost, node.total_cost, node.plan_rows
+    );
+    if let Some(rows) = node.actual_rows {
+        out.push_str(&format!(" actual={rows}"));
+    }
+    if let Some(loops) = node.actual_loops.filter(|l| *l > 1.0) {
+        out.push_str(&format!(" loops={loops}"));
+    }
+    if let Some(ms) = node.total_ms() {
+        out.push_str(&format!(" time={ms:.3} ms"));
+    }
+    out.push_str(&format!(" ({:.0}%)", share * 100.0));
+    if let Some(b) = node.buffers {
+        out.push_str(&format!(" hit={} read={}", b.shared_hit, b.shared_read));
+        if b.temp_read > 0 || b.temp_written > 0 {
+            out.push_str(&format!(" temp={}/{}", b.temp_read, b.temp_written));
+        }
+    }
+    out
+}
+
+/// Server diagnostics for the last failed command. When the statement is
+/// known, `position` is shown as the offending line with a caret under it.
+/// Returns the panel and the height it needs, borders included.
+pub fn error_panel(err: &DbError, sql: Option<&str>, theme: &Theme) -> (Paragraph<'static>, u16) {
+    let label = theme.error.add_modifier(Modifier::BOLD);
+
+    let head = match &err.code {
+        Some(code) => format!("ERROR {code}: "),
+        None => "ERROR: ".to_string(),
+    };
+    let mut lines = vec![Line::from(vec![
+        Span::styled(head, label),
+        Span::styled(err.message.clone(), theme.text),
+    ])];
+
+    for (name, value) in [
+        ("DETAIL: ", &err.detail),
+        ("HINT: ", &err.hint),
+        ("CONSTRAINT: ", &err.constraint),
+    ] {
+        if let Some(v) = value {
+            lines.push(Line::from(vec![
+                Span::styled(name, label),
+                Span::styled(v.clone(), theme.text),
+            ]));
+        }
+    }
+
+    // Position counts characters from 1.
+    let at = sql.zip(err.position).and_then(|(sql, pos)| {
+        let byte = sql.char_indices().nth(pos.checked_sub(1)?)?.0;
+        let (line, col) = cursor_line_col(sql, byte);
+        Some((line, col, sql.lines().nth(line)?.to_string()))
+    });
+    if let Some((line, col, text)) = at {
+        let prefix = format!("LINE {}: ", line + 1);
+        let caret = format!("{}^", " ".repeat(prefix.chars().count() + col));
+        lines.push(Line::from(vec![
+            Span::styled(prefix, label),
+            Span::styled(text, theme.text),
+        ]));
+        lines.push(Line::from(Span::styled(caret, theme.error)));
+    }
+
+    let height = lines.len() as u16 + 2;
+    let title = Line::from(Span::styled(" Error (Esc to dismiss) ", label));
+    let panel = Paragraph::new(Text::from(lines)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.error)
+            .title(title)
+            .style(theme.text),
+    );
+    (panel, height)
+}
+
+/// One line per finished statement of the last script, newest at the bottom.
+/// Returns the panel and the height it needs, borders included.
+pub fn script_log(
+    entries: &[StatementLog],
+    max_lines: usize,
+    theme: &Theme,
+) -> (List<'static>, u16) {
+    let skip = entries.len().saturating_sub(max_lines);
+    let items: Vec<ListItem> = entries
+        .iter()
+        .enumerate()
+        .skip(skip)
+        .map(|(i, e)| {
+            let (summary, style) = match &e.outcome {
+                StatementOutcome::Rows { total, .. } => (format!("{total} rows"), theme.text),
+                StatementOutcome::Command { tag } => (tag.clone(), theme.text),
+                StatementOutcome::Failed(err) => (format!("ERROR {err}"), theme.error),
+                StatementOutcome::Cancelled { .. } => ("cancelled".to_string(), theme.muted),
+            };
+            let first_line = e.sql.lines().next().unwrap_or_default().to_string();
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:>3}. ", i + 1), theme.muted),
+                Span::styled(summary, style),
+                Span::styled(
+                    format!("  {:.1} ms  ", e.elapsed.as_secs_f64() * 1000.0),
+                    theme.muted,
+                ),
+                Span::styled(first_line, theme.muted),
+            ]))
+        })
+        .collect();
+
+    let height = items.len() as u16 + 2;
+    let title = title_pill("Script", false, theme);
+    (
+        List::new(items).block(block_with_border(title, false, theme)),
+        height,
+    )
+}
+
+/// Asks whether to commit or roll back before quitting. Returns the prompt and its size.
+pub fn quit_prompt(tx: TxStatus, theme: &Theme) -> (Paragraph<'static>, u16, u16) {
+    let head = if tx == TxStatus::Failed {
+        "The open transaction has failed and can only be rolled back."
+    } else {
+        "A transaction is still open."
+    };
+    let lines = vec![
+        Line::from(Span::styled(head, theme.text)),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("c", theme.tab_active),
+            Span::styled(" commit and quit   ", theme.text),
+            Span::styled("r", theme.tab_active),
+            Span::styled(" roll back and quit   ", theme.text),
+            Span::styled("Esc", theme.tab_active),
+            Span::styled(" stay", theme.text),
+        ]),
+    ];
+    let title = title_pill("Quit", true, theme);
+    let prompt = Paragraph::new(Text::from(lines))
+        .alignment(Alignment::Center)
+        .block(block_with_border(title, true, theme));
+    (prompt, 64, 5)
+}
+
+/// Asks for the values of a script's placeholders, one line each:
+/// name, type where known, value (NULL when unset).
+/// Returns the form and its size.
+pub fn param_form(form: &ParamForm, theme: &Theme) -> (Paragraph<'static>, u16, u16) {
+    let label = |name: &str| {
+        if name.chars().all(|c| c.is_ascii_digit()) {
+            format!("${name}")
+        } else {
+            format!(":{name}")
+        }
+    };
+    let label_width = form
+        .params
+        .iter()
+        .map(|p| label(&p.name).chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut lines: Vec<Line> = form
+        .params
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let selected = i == form.selected;
+            let marker = if selected { "▶ " } else { "  " };
+            let mut spans = vec![
+                Span::styled(marker, theme.text),
+                Span::styled(
+                    format!("{:<label_width$}", label(&p.name)),
+                    theme.table_header,
+                ),
+            ];
+            if let Some(ty) = &p.type_name {
+                spans.push(Span::styled(format!(" {ty}"), theme.muted));
+            }
+            spans.push(Span::raw("  "));
+            spans.push(match &p.value {
+                Some(v) if selected => Span::styled(format!("{v}▏"), theme.editor_text),
+                Some(v) => Span::styled(v.clone(), theme.text),
+                None => Span::styled("NULL", theme.muted.add_modifier(Modifier::ITALIC)),
+            });
+            Line::from(spans)
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Enter run  Tab next  Backspace on empty = NULL  PgUp/PgDn recall  Esc cancel",
+        theme.muted,
+    )));
+
+    let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 4;
+    let height = lines.len() as u16 + 2;
+    let title = title_pill("Parameters", true, theme);
+    let panel = Paragraph::new(Text::from(lines)).block(block_with_border(title, true, theme));
+    (panel, width.max(40), height)
+}
+
+/// The schema picker; returns the list with the width and height it wants.
+pub fn schema_picker<'a>(schemas: &'a [String], theme: &Theme) -> (List<'a>, u16, u16) {
+    let items: Vec<ListItem> = schemas
+        .iter()
+        .map(|s| ListItem::new(Line::from(Span::styled(s.as_str(), theme.list_item))))
+        .collect();
+    let width = schemas.iter().map(|s| s.chars().count()).max().unwrap_or(0) as u16 + 6;
+
+    let title = title_pill("Schema", true, theme);
+    let list = List::new(items)
+        .block(block_with_border(title, true, theme))
+        .style(theme.text)
+        .highlight_style(theme.list_item_selected)
+        .highlight_symbol("▶ ");
+    (list, width.max(24), schemas.len().min(12) as u16 + 2)
+}
+
+/// A `width` x `height` rect centered in `area`, clipped to it.
+pub fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}